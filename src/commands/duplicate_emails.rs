@@ -0,0 +1,54 @@
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    Database,
+};
+
+// lists users sharing the same normalized email,
+// they have to be resolved manually before unique email index can be created
+pub async fn run(db: &Database) {
+    let pipeline = vec![
+        doc! {
+            "$group": {
+                "_id": { "$toLower": { "$trim": { "input": "$email" } } },
+                "users": { "$push": { "_id": "$_id", "email": "$email" } },
+                "count": { "$sum": 1 },
+            }
+        },
+        doc! { "$match": { "count": { "$gt": 1 } } },
+        doc! { "$sort": { "_id": 1 } },
+    ];
+
+    let cursor = db
+        .collection::<Document>("users")
+        .aggregate(pipeline, None)
+        .await
+        .expect("Failed to aggregate users");
+
+    let duplicates: Vec<Document> = cursor
+        .try_collect()
+        .await
+        .expect("Failed to read aggregation result");
+
+    if duplicates.is_empty() {
+        println!("find-duplicate-emails: no duplicates found");
+        return;
+    }
+
+    for duplicate in &duplicates {
+        println!(
+            "find-duplicate-emails: \"{}\" is used by {} users:",
+            duplicate.get_str("_id").unwrap_or_default(),
+            duplicate.get_i32("count").unwrap_or_default()
+        );
+
+        for user in duplicate.get_array("users").into_iter().flatten() {
+            println!("  {}", user);
+        }
+    }
+
+    println!(
+        "find-duplicate-emails: {} emails have duplicates",
+        duplicates.len()
+    );
+}
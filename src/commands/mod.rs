@@ -0,0 +1,39 @@
+use mongodb::Database;
+
+use crate::{jobs, migrations};
+
+pub mod duplicate_emails;
+pub mod repair_like_counts;
+pub mod repair_user_auths;
+
+// one-off maintenance commands, run as `cargo run -- <command> [args]`;
+// returns false when there is no such command
+pub async fn run(command: &str, args: &[String], db: &Database) -> bool {
+    match command {
+        "find-duplicate-emails" => duplicate_emails::run(db).await,
+        "repair-user-auths" => repair_user_auths::run(db).await,
+        "repair-like-counts" => repair_like_counts::run(db).await,
+        "migrate" => migrate(args, db).await,
+        "purge-deleted" => jobs::purge_deleted::run(db)
+            .await
+            .expect("Failed to purge deleted documents"),
+        _ => return false,
+    }
+
+    true
+}
+
+// `migrate` applies pending migrations, `migrate down` reverts last one, `migrate status` lists them
+async fn migrate(args: &[String], db: &Database) {
+    let result = match args.first().map(String::as_str) {
+        None | Some("up") => migrations::up(db).await,
+        Some("down") => migrations::down(db).await,
+        Some("status") => migrations::status(db).await,
+        Some(action) => {
+            eprintln!("Unknown migrate action \"{}\"", action);
+            std::process::exit(1);
+        }
+    };
+
+    result.expect("Failed to run migrations");
+}
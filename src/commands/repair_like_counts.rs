@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document},
+    Database,
+};
+
+use crate::models::{db::Model, Post, Reaction};
+
+// without transactions like_count is changed after reaction is stored,
+// so failure in between leaves it off; recounts it from reactions
+pub async fn run(db: &Database) {
+    let posts = db.collection::<Document>(Post::COLLECTION_NAME);
+    let reactions = db.collection::<Document>(Reaction::COLLECTION_NAME);
+
+    let counts: HashMap<ObjectId, i64> = reactions
+        .aggregate(
+            vec![doc! { "$group": { "_id": "$post_id", "count": { "$sum": 1 } } }],
+            None,
+        )
+        .await
+        .expect("Failed to aggregate reactions")
+        .try_collect::<Vec<Document>>()
+        .await
+        .expect("Failed to read aggregation result")
+        .into_iter()
+        .filter_map(|group| {
+            let count = group.get_i32("count").ok()?;
+            Some((group.get_object_id("_id").ok()?, count as i64))
+        })
+        .collect();
+
+    let records: Vec<Document> = posts
+        .find(None, None)
+        .await
+        .expect("Failed to query posts")
+        .try_collect()
+        .await
+        .expect("Failed to read posts");
+
+    let mut repaired = 0;
+
+    for record in &records {
+        let Ok(post_id) = record.get_object_id("_id") else {
+            continue;
+        };
+        let count = counts.get(&post_id).copied().unwrap_or(0);
+
+        let stored_count = match record.get("like_count") {
+            Some(Bson::Int32(count)) => Some(*count as i64),
+            Some(Bson::Int64(count)) => Some(*count),
+            _ => None,
+        };
+        if stored_count == Some(count) {
+            continue;
+        }
+
+        posts
+            .update_one(
+                doc! { "_id": post_id },
+                doc! { "$set": { "like_count": count } },
+                None,
+            )
+            .await
+            .expect("Failed to update post");
+
+        repaired += 1;
+    }
+
+    println!(
+        "repair-like-counts: checked {}, repaired {}",
+        records.len(),
+        repaired
+    );
+}
@@ -0,0 +1,91 @@
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document},
+    Database,
+};
+
+// user_auths created before linkage fix store user id as quoted string (`"<hex>"`),
+// so login can't find them; rewrites them to user id in the same form users collection has it
+pub async fn run(db: &Database) {
+    let users = db.collection::<Document>("users");
+    let user_auths = db.collection::<Document>("user_auths");
+
+    let records: Vec<Document> = user_auths
+        .find(None, None)
+        .await
+        .expect("Failed to query user_auths")
+        .try_collect()
+        .await
+        .expect("Failed to read user_auths");
+
+    let mut repaired = 0;
+    let mut unrecoverable = 0;
+
+    for record in &records {
+        let auth_id = record.get("_id").cloned().unwrap_or(Bson::Null);
+        let stored_user_id = record.get("user_id").cloned().unwrap_or(Bson::Null);
+
+        let Some(user_id) = parse_user_id(&stored_user_id) else {
+            println!(
+                "repair-user-auths: user_auth {} has unrecognized user_id {}",
+                auth_id, stored_user_id
+            );
+            unrecoverable += 1;
+            continue;
+        };
+
+        // users may still have hex string ids if object id migration is not applied yet
+        let user = users
+            .find_one(doc! { "_id": { "$in": [user_id, user_id.to_hex()] } }, None)
+            .await
+            .expect("Failed to query users");
+
+        let Some(user_id) = user.and_then(|user| user.get("_id").cloned()) else {
+            println!(
+                "repair-user-auths: user_auth {} points to missing user {}",
+                auth_id, user_id
+            );
+            unrecoverable += 1;
+            continue;
+        };
+
+        if user_id == stored_user_id {
+            continue;
+        }
+
+        user_auths
+            .update_one(
+                doc! { "_id": auth_id },
+                doc! { "$set": { "user_id": user_id } },
+                None,
+            )
+            .await
+            .expect("Failed to update user_auth");
+
+        repaired += 1;
+    }
+
+    println!(
+        "repair-user-auths: checked {}, repaired {}, unrecoverable {}",
+        records.len(),
+        repaired,
+        unrecoverable
+    );
+}
+
+// accepts ObjectId, plain hex, quoted hex and `ObjectId("<hex>")` forms
+fn parse_user_id(stored_user_id: &Bson) -> Option<ObjectId> {
+    let stored_user_id = match stored_user_id {
+        Bson::ObjectId(id) => return Some(*id),
+        Bson::String(id) => id,
+        _ => return None,
+    };
+
+    let unwrapped = stored_user_id
+        .trim()
+        .trim_start_matches("ObjectId(")
+        .trim_end_matches(')')
+        .trim_matches('"');
+
+    ObjectId::parse_str(unwrapped).ok()
+}
@@ -9,8 +9,21 @@ pub struct MongoConfig {
 pub struct ApiConfig {
     pub port: u16,
     pub jwt_secret: String,
+    pub jwt_algorithm: String,
+    pub jwt_key_id: String,
+    pub jwt_private_key_path: Option<String>,
+    // (kid, path) pairs of keys accepted for verification, includes previous keys while rotating
+    pub jwt_public_keys: Vec<(String, String)>,
     pub thread_count: Option<usize>,
     pub hash_salt: String,
+    pub access_token_ttl: u64,
+    pub refresh_token_ttl: u64,
+    // seconds soft-deleted documents are kept before purge job removes them
+    pub deleted_retention: u64,
+    // when disabled migrations are applied only with `cargo run -- migrate`
+    pub migrate_on_startup: bool,
+    // used only by integration tests, see utils::fail_point
+    pub fail_point: Option<String>,
 }
 
 #[derive(Debug)]
@@ -1,4 +1,3 @@
-use dotenv;
 use std::sync::OnceLock;
 
 mod config_struct;
@@ -24,15 +23,46 @@ pub fn get() -> &'static Config {
                 },
                 jwt_secret: std::env::var("JWT_SECRET")
                     .unwrap_or_else(|_e| "dontusedefaultkeys".to_string()),
+                jwt_algorithm: std::env::var("JWT_ALGORITHM").unwrap_or("HS512".to_string()),
+                jwt_key_id: std::env::var("JWT_KEY_ID").unwrap_or("default".to_string()),
+                jwt_private_key_path: std::env::var("JWT_PRIVATE_KEY_PATH").ok(),
+                // format is "kid1=path/to/key1.pem,kid2=path/to/key2.pem"
+                jwt_public_keys: match std::env::var("JWT_PUBLIC_KEYS") {
+                    Ok(keys) => keys
+                        .split(',')
+                        .filter(|pair| !pair.trim().is_empty())
+                        .map(|pair| {
+                            let (kid, path) = pair
+                                .split_once('=')
+                                .expect("JWT_PUBLIC_KEYS should contain kid=path pairs");
+                            (kid.trim().to_string(), path.trim().to_string())
+                        })
+                        .collect(),
+                    Err(_) => vec![],
+                },
                 thread_count: match std::env::var("THREAD_COUNT") {
-                    Ok(count) => match count.parse::<usize>() {
-                        Ok(count) => Some(count),
-                        Err(_) => None,
-                    },
+                    Ok(count) => count.parse::<usize>().ok(),
                     Err(_) => None,
                 },
                 hash_salt: std::env::var("HASH_SALT")
                     .unwrap_or_else(|_e| "dontusedefaultsalt".to_string()),
+                access_token_ttl: match std::env::var("ACCESS_TOKEN_TTL") {
+                    Ok(ttl) => ttl.parse::<u64>().unwrap(),
+                    Err(_) => 15 * 60,
+                },
+                refresh_token_ttl: match std::env::var("REFRESH_TOKEN_TTL") {
+                    Ok(ttl) => ttl.parse::<u64>().unwrap(),
+                    Err(_) => 30 * 24 * 60 * 60,
+                },
+                deleted_retention: match std::env::var("DELETED_RETENTION") {
+                    Ok(retention) => retention.parse::<u64>().unwrap(),
+                    Err(_) => 30 * 24 * 60 * 60,
+                },
+                migrate_on_startup: match std::env::var("MIGRATE_ON_STARTUP") {
+                    Ok(migrate) => migrate.parse::<bool>().unwrap(),
+                    Err(_) => true,
+                },
+                fail_point: std::env::var("FAIL_POINT").ok(),
             },
         }
     })
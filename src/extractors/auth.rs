@@ -0,0 +1,81 @@
+use actix_web::{dev::Payload, web, FromRequest, HttpMessage, HttpRequest};
+use futures::future::LocalBoxFuture;
+
+use crate::{
+    models::{user::Role, User},
+    utils::errors::ApiError,
+    AppState,
+};
+
+// any authenticated user; request is rejected with 401 before handler runs otherwise
+#[derive(Debug)]
+pub struct AuthUser(pub User);
+
+// authenticated user with Role::Admin
+#[derive(Debug)]
+pub struct AdminUser(pub User);
+
+// authenticated user when Authorization header is sent, anonymous caller otherwise;
+// invalid token is still rejected with 401, so client notices it
+#[derive(Debug)]
+pub struct MaybeUser(pub Option<User>);
+
+impl FromRequest for AuthUser {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { authenticate(&req).await.map(AuthUser) })
+    }
+}
+
+impl FromRequest for AdminUser {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let user = authenticate(&req).await?;
+
+            if !user.role.satisfy(Role::Admin) {
+                return Err(ApiError::Unauthorized);
+            }
+
+            Ok(AdminUser(user))
+        })
+    }
+}
+
+impl FromRequest for MaybeUser {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            if !req.headers().contains_key("Authorization") {
+                return Ok(MaybeUser(None));
+            }
+
+            authenticate(&req).await.map(|user| MaybeUser(Some(user)))
+        })
+    }
+}
+
+// user is stored in request extensions, so guard middleware and handler extractor share one lookup
+async fn authenticate(req: &HttpRequest) -> Result<User, ApiError> {
+    if let Some(user) = req.extensions().get::<User>() {
+        return Ok(user.clone());
+    }
+
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return Err(ApiError::Internal);
+    };
+
+    let user = state.i.user_service().get_user_from_req(req).await?;
+    req.extensions_mut().insert(user.clone());
+
+    Ok(user)
+}
@@ -0,0 +1,5 @@
+pub mod auth;
+pub mod validated_json;
+
+pub use auth::{AdminUser, AuthUser, MaybeUser};
+pub use validated_json::ValidatedJson;
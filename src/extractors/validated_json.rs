@@ -0,0 +1,72 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use validator::{Validate, ValidationErrors};
+
+use crate::utils::errors::ApiError;
+
+// same as web::Json, but body is also checked by validator and rejected with 422 when invalid
+#[derive(Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<T>::from_request(req, payload);
+
+        Box::pin(async move {
+            let value = json.await?.into_inner();
+
+            if let Err(e) = value.validate() {
+                return Err(ApiError::Validation(validation_details(&e)).into());
+            }
+
+            Ok(ValidatedJson(value))
+        })
+    }
+}
+
+// { "field": [{ "code": "length", "message": "..." }] }
+fn validation_details(errors: &ValidationErrors) -> serde_json::Value {
+    let fields: BTreeMap<_, _> = errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, field_errors)| {
+            let field_errors: Vec<_> = field_errors
+                .iter()
+                .map(|e| {
+                    json!({
+                        "code": e.code,
+                        "message": e.message,
+                    })
+                })
+                .collect();
+
+            (field, field_errors)
+        })
+        .collect();
+
+    json!(fields)
+}
@@ -7,20 +7,42 @@ use crate::services;
 
 pub async fn new() -> Injector {
     let db = models::db::connect().await;
+    let supports_transactions = models::db::supports_transactions(&db).await;
     let db_rc = Rc::new(db.clone());
     let auth_service = Rc::new(services::AuthService::new());
 
+    let token_service = Rc::new(services::TokenService::new(
+        Rc::clone(&db_rc),
+        auth_service.clone(),
+    ));
     let user_service = Rc::new(services::UserService::new(
         Rc::clone(&db_rc),
         auth_service.clone(),
+        token_service.clone(),
+        supports_transactions,
+    ));
+    let post_service = Rc::new(services::PostService::new(
+        Rc::clone(&db_rc),
+        supports_transactions,
+    ));
+    let reaction_service = Rc::new(services::ReactionService::new(
+        Rc::clone(&db_rc),
+        post_service.clone(),
+        supports_transactions,
+    ));
+    let comment_service = Rc::new(services::CommentService::new(
+        Rc::clone(&db_rc),
+        post_service.clone(),
     ));
-    let post_service = Rc::new(services::PostService::new(Rc::clone(&db_rc)));
 
     Injector {
         single_db: Rc::clone(&db_rc),
         single_auth_service: auth_service,
         single_user_service: user_service,
         single_post_service: post_service,
+        single_comment_service: comment_service,
+        single_reaction_service: reaction_service,
+        single_token_service: token_service,
     }
 }
 
@@ -32,6 +54,9 @@ pub struct Injector {
     single_auth_service: Rc<services::auth::AuthService>,
     single_user_service: Rc<services::user::UserService>,
     single_post_service: Rc<services::post::PostService>,
+    single_comment_service: Rc<services::comment::CommentService>,
+    single_reaction_service: Rc<services::reaction::ReactionService>,
+    single_token_service: Rc<services::token::TokenService>,
 }
 
 impl Injector {
@@ -43,7 +68,19 @@ impl Injector {
         &self.single_post_service
     }
 
+    pub fn comment_service(&'_ self) -> &'_ services::comment::CommentService {
+        &self.single_comment_service
+    }
+
+    pub fn reaction_service(&'_ self) -> &'_ services::reaction::ReactionService {
+        &self.single_reaction_service
+    }
+
+    pub fn token_service(&'_ self) -> &'_ services::token::TokenService {
+        &self.single_token_service
+    }
+
     pub fn auth_service(&'_ self) -> &'_ services::auth::AuthService {
-        return &self.single_auth_service;
+        &self.single_auth_service
     }
 }
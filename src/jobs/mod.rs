@@ -0,0 +1,10 @@
+use mongodb::Database;
+
+pub mod publish_scheduled;
+pub mod purge_deleted;
+
+// background jobs running inside api process next to http server
+pub fn spawn_all(db: &Database) {
+    actix_web::rt::spawn(purge_deleted::schedule(db.clone()));
+    actix_web::rt::spawn(publish_scheduled::schedule(db.clone()));
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use mongodb::{
+    bson::{doc, Document},
+    Database,
+};
+
+use crate::models::{db::Model, post::PostStatus, DbError, Post, Timestamp};
+
+const INTERVAL: Duration = Duration::from_secs(60);
+
+pub async fn schedule(db: Database) {
+    let mut interval = actix_web::rt::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = run(&db).await {
+            println!("publish-scheduled: failed: {}", e);
+        }
+    }
+}
+
+// scheduled posts are visible from publish_at anyway, job only keeps their status accurate
+pub async fn run(db: &Database) -> Result<(), DbError> {
+    let result = db
+        .collection::<Document>(Post::COLLECTION_NAME)
+        .update_many(
+            doc! {
+                "status": PostStatus::Scheduled,
+                "publish_at": { "$lte": Timestamp::now() },
+            },
+            doc! { "$set": { "status": PostStatus::Published } },
+            None,
+        )
+        .await?;
+
+    if result.modified_count > 0 {
+        println!(
+            "publish-scheduled: published {} posts",
+            result.modified_count
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, DateTime, Document},
+    Collection, Database,
+};
+
+use crate::{
+    config,
+    models::{
+        db::Model, user::UserAuth, Comment, DbError, Post, PostRevision, Reaction, RefreshToken,
+        RevokedToken, User,
+    },
+};
+
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub async fn schedule(db: Database) {
+    let mut interval = actix_web::rt::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = run(&db).await {
+            println!("purge-deleted: failed: {}", e);
+        }
+    }
+}
+
+// hard-deletes documents soft-deleted longer than retention period ago,
+// everything created by removed users goes along with them
+pub async fn run(db: &Database) -> Result<(), DbError> {
+    let retention = Duration::from_secs(config::get().api.deleted_retention);
+    let deleted_before =
+        DateTime::from_millis(DateTime::now().timestamp_millis() - retention.as_millis() as i64);
+    let expired = doc! { "deleted_at": { "$ne": Bson::Null, "$lt": deleted_before } };
+
+    let users = db.collection::<Document>(User::COLLECTION_NAME);
+    let user_ids = expired_ids(&users, &expired).await?;
+    let by_removed_users = doc! { "user_id": { "$in": &user_ids } };
+
+    let posts = db.collection::<Document>(Post::COLLECTION_NAME);
+    let post_ids = expired_ids(
+        &posts,
+        &doc! { "$or": [expired.clone(), by_removed_users.clone()] },
+    )
+    .await?;
+
+    // replies to comments of removed users are removed with them, same as on comment delete
+    let comments = db.collection::<Document>(Comment::COLLECTION_NAME);
+    let user_comment_ids = expired_ids(&comments, &by_removed_users).await?;
+    let removed_comments = find_all(
+        &comments,
+        doc! {
+            "$or": [
+                expired.clone(),
+                by_removed_users.clone(),
+                { "post_id": { "$in": &post_ids } },
+                { "ancestors": { "$in": &user_comment_ids } },
+            ]
+        },
+    )
+    .await?;
+    let removed_comment_ids = field_values(&removed_comments, "_id");
+
+    let comments_result = comments
+        .delete_many(doc! { "_id": { "$in": &removed_comment_ids } }, None)
+        .await?;
+
+    let reactions = db.collection::<Document>(Reaction::COLLECTION_NAME);
+    let removed_reactions = find_all(&reactions, by_removed_users.clone()).await?;
+
+    reactions
+        .delete_many(
+            doc! {
+                "$or": [
+                    by_removed_users.clone(),
+                    { "post_id": { "$in": &post_ids } },
+                ]
+            },
+            None,
+        )
+        .await?;
+
+    let revisions = db.collection::<Document>(PostRevision::COLLECTION_NAME);
+    revisions
+        .delete_many(doc! { "post_id": { "$in": &post_ids } }, None)
+        .await?;
+
+    // edits removed users made to posts of others stay in history without author
+    revisions
+        .update_many(
+            doc! { "created_by": { "$in": &user_ids } },
+            doc! { "$set": { "created_by": Bson::Null } },
+            None,
+        )
+        .await?;
+
+    let posts_result = posts
+        .delete_many(doc! { "_id": { "$in": &post_ids } }, None)
+        .await?;
+
+    // posts and comments left in place lose counted comments and likes of removed ones
+    let remaining = |ids: Vec<Bson>| -> Vec<Bson> {
+        ids.into_iter()
+            .filter(|id| !post_ids.contains(id) && !removed_comment_ids.contains(id))
+            .collect()
+    };
+
+    for post_id in remaining(field_values(&removed_comments, "post_id")) {
+        let count = comments
+            .count_documents(doc! { "post_id": &post_id, "deleted_at": Bson::Null }, None)
+            .await?;
+        set_counter(&posts, &post_id, "comment_count", count).await?;
+    }
+
+    for parent_id in remaining(field_values(&removed_comments, "parent_id")) {
+        let count = comments
+            .count_documents(
+                doc! { "parent_id": &parent_id, "deleted_at": Bson::Null },
+                None,
+            )
+            .await?;
+        set_counter(&comments, &parent_id, "reply_count", count).await?;
+    }
+
+    for post_id in remaining(field_values(&removed_reactions, "post_id")) {
+        let count = reactions
+            .count_documents(doc! { "post_id": &post_id }, None)
+            .await?;
+        set_counter(&posts, &post_id, "like_count", count).await?;
+    }
+
+    db.collection::<Document>(RefreshToken::COLLECTION_NAME)
+        .delete_many(by_removed_users.clone(), None)
+        .await?;
+
+    db.collection::<Document>(RevokedToken::COLLECTION_NAME)
+        .delete_many(by_removed_users.clone(), None)
+        .await?;
+
+    // credentials go first, so user is never left able to sign in without profile
+    db.collection::<Document>(UserAuth::COLLECTION_NAME)
+        .delete_many(by_removed_users, None)
+        .await?;
+
+    let users_result = users
+        .delete_many(doc! { "_id": { "$in": &user_ids } }, None)
+        .await?;
+
+    println!(
+        "purge-deleted: removed {} comments, {} posts and {} users",
+        comments_result.deleted_count, posts_result.deleted_count, users_result.deleted_count
+    );
+
+    Ok(())
+}
+
+async fn set_counter(
+    collection: &Collection<Document>,
+    id: &Bson,
+    counter: &str,
+    count: u64,
+) -> Result<(), DbError> {
+    collection
+        .update_one(
+            doc! { "_id": id },
+            doc! { "$set": { counter: count as i64 } },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn find_all(
+    collection: &Collection<Document>,
+    filter: Document,
+) -> Result<Vec<Document>, DbError> {
+    collection.find(filter, None).await?.try_collect().await
+}
+
+// distinct non-null values of `field` among documents
+fn field_values(documents: &[Document], field: &str) -> Vec<Bson> {
+    let mut values: Vec<Bson> = vec![];
+
+    for value in documents.iter().filter_map(|document| document.get(field)) {
+        if *value != Bson::Null && !values.contains(value) {
+            values.push(value.clone());
+        }
+    }
+
+    values
+}
+
+async fn expired_ids(
+    collection: &Collection<Document>,
+    expired: &Document,
+) -> Result<Vec<Bson>, DbError> {
+    let ids = collection
+        .find(expired.clone(), None)
+        .await?
+        .try_collect::<Vec<Document>>()
+        .await?
+        .into_iter()
+        .filter_map(|document| document.get("_id").cloned())
+        .collect();
+
+    Ok(ids)
+}
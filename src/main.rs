@@ -1,17 +1,47 @@
 mod app_state;
+mod commands;
 mod config;
+mod extractors;
 mod injector;
+mod jobs;
+mod middleware;
+mod migrations;
 mod models;
 mod routes;
 mod services;
 mod utils;
-use actix_web::{App, HttpServer};
+use actix_web::{web, App, HttpServer};
+use actix_web_lab::middleware::from_fn;
 pub use app_state::AppState;
+use utils::errors;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let workers_count = config::get().api.thread_count;
 
+    let db = models::db::connect().await;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some((command, command_args)) = args.split_first() {
+        if !commands::run(command, command_args, &db).await {
+            eprintln!("Unknown command \"{}\"", command);
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    if config::get().api.migrate_on_startup {
+        migrations::up(&db)
+            .await
+            .expect("Failed to apply migrations");
+    }
+
+    models::db::sync_indexes(&db).await;
+
+    jobs::spawn_all(&db);
+
     let mut server = HttpServer::new(|| {
         App::new()
             .data_factory(|| async {
@@ -20,9 +50,20 @@ async fn main() -> std::io::Result<()> {
 
                 Ok::<_, AppState>(app_state)
             })
+            .wrap(from_fn(middleware::request_id::request_id))
+            .app_data(
+                web::JsonConfig::default()
+                    .limit(services::post::MAX_PAYLOAD_SIZE)
+                    .error_handler(errors::json_error_handler),
+            )
+            .app_data(web::PathConfig::default().error_handler(errors::path_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(errors::query_error_handler))
             .service(routes::status::scope())
             .service(routes::users::scope())
             .service(routes::posts::scope())
+            .service(routes::tags::scope())
+            .service(routes::well_known::scope())
+            .default_service(web::to(routes::not_found))
     });
 
     if workers_count.is_some() {
@@ -0,0 +1,18 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    Error,
+};
+use actix_web_lab::middleware::Next;
+
+use crate::extractors::AdminUser;
+
+// guard rejecting non-admins before handler runs, wrap each admin route with it
+// (`wrap = "from_fn(require_admin)"`): wrapped catch-all scope would also swallow other routes
+pub async fn require_admin(
+    _admin: AdminUser,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    next.call(req).await
+}
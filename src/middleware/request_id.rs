@@ -0,0 +1,53 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    Error,
+};
+use actix_web_lab::middleware::Next;
+use mongodb::bson::oid::ObjectId;
+
+use crate::utils::errors::ApiError;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// tags every request with id (taken from incoming header or generated),
+// returns it in response header and in body of ApiError responses
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| ObjectId::new().to_hex());
+
+    let http_req = req.request().clone();
+
+    // errors raised by middleware (e.g. auth guards) come as Err instead of error response
+    let res = match next.call(req).await {
+        Ok(res) => res.map_into_boxed_body(),
+        Err(e) => ServiceResponse::from_err(e, http_req),
+    };
+
+    let api_error_response = res
+        .response()
+        .error()
+        .and_then(|e| e.as_error::<ApiError>())
+        .map(|api_error| api_error.to_response(Some(&request_id)));
+
+    let mut res = match api_error_response {
+        Some(api_error_response) => res.into_response(api_error_response),
+        None => res,
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,51 @@
+use futures::FutureExt;
+use mongodb::error::ErrorKind;
+
+use crate::{
+    migrations::Migration,
+    models::{Database, DbError},
+};
+
+// NamespaceExists
+const COLLECTION_EXISTS_CODE: i32 = 48;
+
+const COLLECTIONS: [&str; 5] = [
+    "users",
+    "user_auths",
+    "posts",
+    "refresh_tokens",
+    "revoked_tokens",
+];
+
+pub fn migration() -> Migration {
+    Migration {
+        version: 1,
+        name: "create_collections",
+        up: |db| up(db).boxed_local(),
+        down: |db| down(db).boxed_local(),
+    }
+}
+
+async fn up(db: &Database) -> Result<(), DbError> {
+    for name in COLLECTIONS {
+        match db.create_collection(name, None).await {
+            Ok(_) => {}
+            Err(e) if is_collection_exists_error(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+// collections are kept on revert, dropping them would lose data
+async fn down(_db: &Database) -> Result<(), DbError> {
+    Ok(())
+}
+
+fn is_collection_exists_error(e: &DbError) -> bool {
+    match e.kind.as_ref() {
+        ErrorKind::Command(command_error) => command_error.code == COLLECTION_EXISTS_CODE,
+        _ => false,
+    }
+}
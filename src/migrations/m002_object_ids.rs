@@ -0,0 +1,254 @@
+use futures::{FutureExt, TryStreamExt};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+
+use crate::{
+    migrations::Migration,
+    models::{db, Database, DbError},
+};
+
+// collections with hex string ids and fields referencing users
+const COLLECTIONS: [(&str, &[&str]); 5] = [
+    ("users", &[]),
+    ("user_auths", &["user_id"]),
+    ("posts", &["user_id"]),
+    ("refresh_tokens", &["user_id"]),
+    ("revoked_tokens", &["user_id"]),
+];
+
+pub fn migration() -> Migration {
+    Migration {
+        version: 2,
+        name: "object_ids",
+        up: |db| convert_all(db, "string", "objectId").boxed_local(),
+        down: |db| convert_all(db, "objectId", "string").boxed_local(),
+    }
+}
+
+async fn convert_all(db: &Database, from: &str, to: &str) -> Result<(), DbError> {
+    for (collection_name, reference_fields) in COLLECTIONS {
+        convert_document_ids(db, collection_name, from, to).await?;
+
+        for field in reference_fields {
+            convert_field(db, collection_name, field, from, to).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// values that can't be converted, e.g. malformed legacy ids, are left as is
+async fn convert_field(
+    db: &Database,
+    collection_name: &str,
+    field: &str,
+    from: &str,
+    to: &str,
+) -> Result<(), DbError> {
+    let value = format!("${}", field);
+
+    db.collection::<Document>(collection_name)
+        .update_many(
+            doc! { field: { "$type": from } },
+            vec![doc! {
+                "$set": {
+                    field: { "$convert": { "input": &value, "to": to, "onError": &value } }
+                }
+            }],
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+// _id is immutable, so documents are written back with converted id; that's done in transaction
+// when deployment supports it, and through a copy of collection otherwise, so no document is lost
+// if migration is interrupted
+async fn convert_document_ids(
+    db: &Database,
+    collection_name: &str,
+    from: &str,
+    to: &str,
+) -> Result<(), DbError> {
+    let collection = db.collection::<Document>(collection_name);
+
+    let to_convert = collection
+        .count_documents(doc! { "_id": { "$type": from } }, None)
+        .await?;
+
+    if to_convert == 0 {
+        return Ok(());
+    }
+
+    if db::supports_transactions(db).await {
+        convert_ids_in_transactions(db, collection_name, from, to).await
+    } else {
+        convert_ids_by_copy(db, collection_name, to).await
+    }
+}
+
+// deleting first keeps unique indexes from conflicting with the copy
+async fn convert_ids_in_transactions(
+    db: &Database,
+    collection_name: &str,
+    from: &str,
+    to: &str,
+) -> Result<(), DbError> {
+    let collection = db.collection::<Document>(collection_name);
+
+    let documents: Vec<Document> = collection
+        .find(doc! { "_id": { "$type": from } }, None)
+        .await?
+        .try_collect()
+        .await?;
+
+    for document in documents {
+        let Some((old_id, converted)) = convert_document(collection_name, &document, to) else {
+            continue;
+        };
+
+        db::with_transaction(
+            collection.client(),
+            (&collection, old_id, converted),
+            |session, (collection, old_id, converted)| {
+                Box::pin(async move {
+                    collection
+                        .delete_one_with_session(doc! { "_id": old_id }, None, session)
+                        .await?;
+                    collection
+                        .insert_one_with_session(converted, None, session)
+                        .await?;
+
+                    Ok::<_, DbError>(())
+                })
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// whole collection is copied with converted ids and indexes, then the copy replaces it;
+// original stays untouched until rename, leftover copy of interrupted run is dropped
+async fn convert_ids_by_copy(
+    db: &Database,
+    collection_name: &str,
+    to: &str,
+) -> Result<(), DbError> {
+    let collection = db.collection::<Document>(collection_name);
+    let copy_name = format!("{}_m002_copy", collection_name);
+    let copy = db.collection::<Document>(&copy_name);
+
+    copy.drop(None).await?;
+    db.create_collection(&copy_name, None).await?;
+    copy_indexes(db, collection_name, &copy_name).await?;
+
+    let documents: Vec<Document> = collection.find(None, None).await?.try_collect().await?;
+
+    let converted: Vec<Document> = documents
+        .iter()
+        .map(
+            |document| match convert_document(collection_name, document, to) {
+                Some((_, converted)) => converted,
+                None => document.clone(),
+            },
+        )
+        .collect();
+
+    copy.insert_many(&converted, None).await?;
+
+    let original_count = collection.count_documents(None, None).await?;
+    let copy_count = copy.count_documents(None, None).await?;
+
+    if original_count != documents.len() as u64 || copy_count != original_count {
+        copy.drop(None).await?;
+
+        return Err(DbError::custom(format!(
+            "{} changed while its ids were converted, {} documents were copied out of {}",
+            collection_name, copy_count, original_count
+        )));
+    }
+
+    let namespace = |name: &str| format!("{}.{}", db.name(), name);
+
+    collection
+        .client()
+        .database("admin")
+        .run_command(
+            doc! {
+                "renameCollection": namespace(&copy_name),
+                "to": namespace(collection_name),
+                "dropTarget": true,
+            },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn copy_indexes(db: &Database, from: &str, to: &str) -> Result<(), DbError> {
+    let response = db.run_command(doc! { "listIndexes": from }, None).await?;
+
+    let indexes: Vec<Document> = response
+        .get_document("cursor")
+        .and_then(|cursor| cursor.get_array("firstBatch"))
+        .map(|batch| {
+            batch
+                .iter()
+                .filter_map(Bson::as_document)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let indexes: Vec<Document> = indexes
+        .into_iter()
+        .filter(|index| index.get_str("name") != Ok("_id_"))
+        .map(|mut index| {
+            index.remove("v");
+            index.remove("ns");
+            index
+        })
+        .collect();
+
+    if indexes.is_empty() {
+        return Ok(());
+    }
+
+    db.run_command(doc! { "createIndexes": to, "indexes": indexes }, None)
+        .await?;
+
+    Ok(())
+}
+
+// None when document has no id or id can't be converted, e.g. malformed legacy one
+fn convert_document(
+    collection_name: &str,
+    document: &Document,
+    to: &str,
+) -> Option<(Bson, Document)> {
+    let old_id = document.get("_id")?.clone();
+
+    let Some(new_id) = convert_id(&old_id, to) else {
+        println!(
+            "migrations: {} {} has malformed id, skipping",
+            collection_name, old_id
+        );
+        return None;
+    };
+
+    let mut converted = document.clone();
+    converted.insert("_id", new_id);
+
+    Some((old_id, converted))
+}
+
+fn convert_id(id: &Bson, to: &str) -> Option<Bson> {
+    match (id, to) {
+        (Bson::String(hex), "objectId") => ObjectId::parse_str(hex).ok().map(Bson::ObjectId),
+        (Bson::ObjectId(id), "string") => Some(Bson::String(id.to_hex())),
+        _ => None,
+    }
+}
@@ -0,0 +1,74 @@
+use futures::FutureExt;
+use mongodb::bson::{doc, Document};
+
+use crate::{
+    migrations::Migration,
+    models::{Database, DbError},
+};
+
+// collection and field holding id of user who owns its documents
+const COLLECTIONS: [(&str, &str); 3] = [
+    ("users", "$_id"),
+    ("user_auths", "$user_id"),
+    ("posts", "$user_id"),
+];
+
+pub fn migration() -> Migration {
+    Migration {
+        version: 3,
+        name: "audit_fields",
+        up: |db| up(db).boxed_local(),
+        down: |db| down(db).boxed_local(),
+    }
+}
+
+// creation time is taken from ObjectId, documents with malformed ids get migration time
+async fn up(db: &Database) -> Result<(), DbError> {
+    for (collection_name, owner_field) in COLLECTIONS {
+        db.collection::<Document>(collection_name)
+            .update_many(
+                doc! { "created_at": { "$exists": false } },
+                vec![
+                    doc! {
+                        "$set": {
+                            "created_at": {
+                                "$convert": { "input": "$_id", "to": "date", "onError": "$$NOW" }
+                            },
+                            "created_by": owner_field,
+                        }
+                    },
+                    doc! {
+                        "$set": {
+                            "updated_at": "$created_at",
+                            "updated_by": "$created_by",
+                        }
+                    },
+                ],
+                None,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn down(db: &Database) -> Result<(), DbError> {
+    for (collection_name, _) in COLLECTIONS {
+        db.collection::<Document>(collection_name)
+            .update_many(
+                doc! {},
+                doc! {
+                    "$unset": {
+                        "created_at": "",
+                        "created_by": "",
+                        "updated_at": "",
+                        "updated_by": "",
+                    }
+                },
+                None,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use futures::FutureExt;
+use mongodb::bson::{doc, Document};
+
+use crate::{
+    migrations::Migration,
+    models::{db::Model, Database, DbError, Post},
+};
+
+pub fn migration() -> Migration {
+    Migration {
+        version: 6,
+        name: "post_status",
+        up: |db| up(db).boxed_local(),
+        down: |db| down(db).boxed_local(),
+    }
+}
+
+// every existing post was public since it was created
+async fn up(db: &Database) -> Result<(), DbError> {
+    db.collection::<Document>(Post::COLLECTION_NAME)
+        .update_many(
+            doc! { "status": { "$exists": false } },
+            vec![doc! {
+                "$set": {
+                    "status": "published",
+                    "publish_at": "$created_at",
+                }
+            }],
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn down(db: &Database) -> Result<(), DbError> {
+    db.collection::<Document>(Post::COLLECTION_NAME)
+        .update_many(
+            doc! {},
+            doc! { "$unset": { "status": "", "publish_at": "" } },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
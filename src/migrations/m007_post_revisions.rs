@@ -0,0 +1,73 @@
+use futures::{FutureExt, TryStreamExt};
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+};
+
+use crate::{
+    migrations::Migration,
+    models::{db::Model, Database, DbError, Post, PostRevision},
+};
+
+pub fn migration() -> Migration {
+    Migration {
+        version: 7,
+        name: "post_revisions",
+        up: |db| up(db).boxed_local(),
+        down: |db| down(db).boxed_local(),
+    }
+}
+
+// current content of every post becomes its first revision,
+// upsert keeps migration safe to run again after interruption
+async fn up(db: &Database) -> Result<(), DbError> {
+    let posts = db.collection::<Document>(Post::COLLECTION_NAME);
+    let revisions = db.collection::<Document>(PostRevision::COLLECTION_NAME);
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    let mut cursor = posts
+        .find(doc! { "revision": { "$exists": false } }, None)
+        .await?;
+
+    while let Some(post) = cursor.try_next().await? {
+        let post_id = post.get("_id").cloned();
+
+        revisions
+            .update_one(
+                doc! { "post_id": post_id.clone(), "rev": 1_i64 },
+                doc! {
+                    "$setOnInsert": {
+                        "title": post.get("title").cloned(),
+                        "content": post.get("content").cloned(),
+                        "created_at": post.get("updated_at").cloned(),
+                        "created_by": post.get("updated_by").cloned(),
+                    }
+                },
+                options.clone(),
+            )
+            .await?;
+
+        posts
+            .update_one(
+                doc! { "_id": post_id },
+                doc! { "$set": { "revision": 1_i64 } },
+                None,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+// revisions made after migration depend on post counter, so history can't outlive it
+async fn down(db: &Database) -> Result<(), DbError> {
+    db.collection::<Document>(PostRevision::COLLECTION_NAME)
+        .delete_many(doc! {}, None)
+        .await?;
+
+    db.collection::<Document>(Post::COLLECTION_NAME)
+        .update_many(doc! {}, doc! { "$unset": { "revision": "" } }, None)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use futures::FutureExt;
+use mongodb::bson::{doc, Document};
+
+use crate::{
+    migrations::Migration,
+    models::{db::Model, Database, DbError, Post},
+};
+
+pub fn migration() -> Migration {
+    Migration {
+        version: 8,
+        name: "post_tags",
+        up: |db| up(db).boxed_local(),
+        down: |db| down(db).boxed_local(),
+    }
+}
+
+// posts had no tags before
+async fn up(db: &Database) -> Result<(), DbError> {
+    db.collection::<Document>(Post::COLLECTION_NAME)
+        .update_many(
+            doc! { "tags": { "$exists": false } },
+            doc! { "$set": { "tags": [] } },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn down(db: &Database) -> Result<(), DbError> {
+    db.collection::<Document>(Post::COLLECTION_NAME)
+        .update_many(doc! {}, doc! { "$unset": { "tags": "" } }, None)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,266 @@
+use std::{collections::HashSet, pin::pin, time::Duration};
+
+use futures::{
+    future::{self, Either, LocalBoxFuture},
+    TryStreamExt,
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    options::{FindOptions, UpdateOptions},
+    Collection,
+};
+
+use crate::models::{
+    db,
+    migration::{AppliedMigration, MigrationLock},
+    Database, DbError,
+};
+
+mod m001_create_collections;
+mod m002_object_ids;
+mod m003_audit_fields;
+mod m006_post_status;
+mod m007_post_revisions;
+mod m008_post_tags;
+
+pub type MigrationStep = for<'a> fn(&'a Database) -> LocalBoxFuture<'a, Result<(), DbError>>;
+
+pub struct Migration {
+    // versions are applied in ascending order and should never be reused
+    pub version: i64,
+    pub name: &'static str,
+    pub up: MigrationStep,
+    pub down: MigrationStep,
+}
+
+// every new migration has to be added here
+fn all() -> Vec<Migration> {
+    vec![
+        m001_create_collections::migration(),
+        m002_object_ids::migration(),
+        m003_audit_fields::migration(),
+        // versions 4 and 5 backfilled comment_count and like_count,
+        // serde defaults on Post cover them instead
+        m006_post_status::migration(),
+        m007_post_revisions::migration(),
+        m008_post_tags::migration(),
+    ]
+}
+
+const LOCK_ID: &str = "migrations";
+const LOCK_TTL: Duration = Duration::from_secs(10 * 60);
+// lock is extended this often while migrations run, so it doesn't expire under long migration
+const LOCK_RENEW_INTERVAL: Duration = Duration::from_secs(60);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+// applies every pending migration
+pub async fn up(db: &Database) -> Result<(), DbError> {
+    with_lock(db, || async {
+        let applied = applied_versions(db).await?;
+        let known: HashSet<i64> = all().iter().map(|m| m.version).collect();
+
+        for version in applied.iter().filter(|v| !known.contains(v)) {
+            println!(
+                "migrations: version {} is applied, but unknown to this build",
+                version
+            );
+        }
+
+        let mut pending: Vec<Migration> = all()
+            .into_iter()
+            .filter(|m| !applied.contains(&m.version))
+            .collect();
+        pending.sort_by_key(|m| m.version);
+
+        if pending.is_empty() {
+            println!("migrations: database is up to date");
+            return Ok(());
+        }
+
+        for migration in pending {
+            println!(
+                "migrations: applying {} {}",
+                migration.version, migration.name
+            );
+
+            (migration.up)(db).await?;
+
+            collection(db)
+                .insert_one(
+                    AppliedMigration {
+                        _id: migration.version,
+                        name: migration.name.to_string(),
+                        applied_at: DateTime::now(),
+                    },
+                    None,
+                )
+                .await?;
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+// reverts last applied migration
+pub async fn down(db: &Database) -> Result<(), DbError> {
+    with_lock(db, || async {
+        let options = FindOptions::builder().sort(doc! { "_id": -1 }).build();
+        let last_applied = collection(db).find(None, options).await?.try_next().await?;
+
+        let Some(last_applied) = last_applied else {
+            println!("migrations: nothing to revert");
+            return Ok(());
+        };
+
+        let Some(migration) = all().into_iter().find(|m| m.version == last_applied._id) else {
+            println!(
+                "migrations: version {} is unknown to this build and can't be reverted",
+                last_applied._id
+            );
+            return Ok(());
+        };
+
+        println!(
+            "migrations: reverting {} {}",
+            migration.version, migration.name
+        );
+
+        (migration.down)(db).await?;
+
+        collection(db)
+            .delete_one(doc! { "_id": migration.version }, None)
+            .await?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn status(db: &Database) -> Result<(), DbError> {
+    let applied = applied_versions(db).await?;
+
+    for migration in all() {
+        let state = if applied.contains(&migration.version) {
+            "applied"
+        } else {
+            "pending"
+        };
+
+        println!(
+            "migrations: {} {} {}",
+            migration.version, migration.name, state
+        );
+    }
+
+    Ok(())
+}
+
+async fn applied_versions(db: &Database) -> Result<HashSet<i64>, DbError> {
+    let applied: Vec<AppliedMigration> =
+        collection(db).find(None, None).await?.try_collect().await?;
+
+    Ok(applied.into_iter().map(|m| m._id).collect())
+}
+
+async fn with_lock<F, Fut>(db: &Database, f: F) -> Result<(), DbError>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<(), DbError>>,
+{
+    let owner = ObjectId::new().to_hex();
+
+    let locked_until = acquire_lock(db, &owner).await?;
+
+    // migrations are stopped once lock is lost, so they never run along with other instance
+    let result = match future::select(pin!(f()), pin!(renew_lock(db, &owner, locked_until))).await {
+        Either::Left((result, _)) => result,
+        Either::Right((lost, _)) => Err(lost),
+    };
+
+    let release_result = lock_collection(db)
+        .delete_one(doc! { "_id": LOCK_ID, "owner": &owner }, None)
+        .await;
+
+    if let Err(e) = release_result {
+        println!("migrations: failed to release lock: {}", e);
+    }
+
+    result
+}
+
+// waits for as long as other instance holds the lock: it's renewed while that instance migrates
+// and expires after LOCK_TTL when it crashed, so waiting ends either way
+async fn acquire_lock(db: &Database, owner: &str) -> Result<DateTime, DbError> {
+    let mut is_waiting = false;
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    loop {
+        let now = DateTime::now();
+        let locked_until = now.saturating_add_duration(LOCK_TTL);
+
+        // upsert fails with duplicate key while other instance holds not expired lock
+        let result = lock_collection(db)
+            .update_one(
+                doc! {
+                    "_id": LOCK_ID,
+                    "locked_until": { "$lt": now },
+                },
+                doc! {
+                    "$set": {
+                        "owner": owner,
+                        "locked_until": locked_until,
+                    }
+                },
+                options.clone(),
+            )
+            .await;
+
+        match result {
+            Ok(_) => return Ok(locked_until),
+            Err(e) if db::is_duplicate_key_error(&e) => {}
+            Err(e) => return Err(e),
+        }
+
+        if !is_waiting {
+            println!("migrations: waiting for lock held by another instance");
+            is_waiting = true;
+        }
+
+        actix_web::rt::time::sleep(LOCK_RETRY_INTERVAL).await;
+    }
+}
+
+// returns only when lock is lost: taken over by other instance or expired while renewal failed
+async fn renew_lock(db: &Database, owner: &str, mut locked_until: DateTime) -> DbError {
+    loop {
+        actix_web::rt::time::sleep(LOCK_RENEW_INTERVAL).await;
+
+        let renewed_until = DateTime::now().saturating_add_duration(LOCK_TTL);
+        let result = lock_collection(db)
+            .update_one(
+                doc! { "_id": LOCK_ID, "owner": owner },
+                doc! { "$set": { "locked_until": renewed_until } },
+                None,
+            )
+            .await;
+
+        match result {
+            Ok(result) if result.matched_count == 0 => {
+                println!("migrations: lock was taken over by another instance");
+                return DbError::custom("migration lock lost");
+            }
+            Ok(_) => locked_until = renewed_until,
+            Err(e) if DateTime::now() >= locked_until => return e,
+            Err(e) => println!("migrations: failed to renew lock: {}", e),
+        }
+    }
+}
+
+fn collection(db: &Database) -> Collection<AppliedMigration> {
+    db.collection("migrations")
+}
+
+fn lock_collection(db: &Database) -> Collection<MigrationLock> {
+    db.collection("migration_locks")
+}
@@ -0,0 +1,35 @@
+use mongodb::bson::{doc, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Timestamp, UserId};
+
+// embedded with #[serde(flatten)] into every audited model;
+// actor is None for writes not made on behalf of a user, e.g. by migrations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Audit {
+    pub created_at: Timestamp,
+    pub created_by: Option<UserId>,
+    pub updated_at: Timestamp,
+    pub updated_by: Option<UserId>,
+}
+
+impl Audit {
+    pub fn new(actor: Option<UserId>) -> Self {
+        let now = Timestamp::now();
+
+        Audit {
+            created_at: now,
+            created_by: actor,
+            updated_at: now,
+            updated_by: actor,
+        }
+    }
+}
+
+// wraps changed fields into $set together with modification stamp
+pub fn set_with_audit(mut changes: Document, actor: Option<UserId>) -> Document {
+    changes.insert("updated_at", Timestamp::now());
+    changes.insert("updated_by", actor);
+
+    doc! { "$set": changes }
+}
@@ -0,0 +1,41 @@
+use mongodb::{bson::doc, IndexModel};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    db::{self, Model},
+    Audit, CommentId, Deletion, PostId, UserId,
+};
+
+// levels of thread including top-level comment, deeper replies are rejected
+pub const MAX_DEPTH: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Comment {
+    pub _id: CommentId,
+    pub post_id: PostId,
+    pub user_id: UserId,
+    // direct parent, is not set for top-level comments
+    pub parent_id: Option<CommentId>,
+    // every comment up the thread starting from top-level one,
+    // so whole subtree can be found with a single query
+    pub ancestors: Vec<CommentId>,
+    pub content: String,
+    pub reply_count: i64,
+    #[serde(flatten)]
+    pub audit: Audit,
+    #[serde(flatten)]
+    pub deletion: Deletion,
+}
+
+impl Model for Comment {
+    const COLLECTION_NAME: &'static str = "comments";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![
+            // listing of post comments, each page is requested by thread parent
+            db::index(doc! { "post_id": 1, "parent_id": 1, "_id": 1 }),
+            db::index(doc! { "ancestors": 1 }),
+            db::index(doc! { "deleted_at": 1 }),
+        ]
+    }
+}
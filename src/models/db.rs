@@ -1,8 +1,34 @@
-use crate::config;
-use mongodb::{Client, Database};
+use std::time::Duration;
+
+use crate::{
+    config,
+    models::{
+        user::UserAuth, Comment, Post, PostRevision, Reaction, RefreshToken, RevokedToken, User,
+    },
+};
+use futures::future::LocalBoxFuture;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    error::{ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR},
+    options::IndexOptions,
+    Client, ClientSession, Database, IndexModel,
+};
 
 pub type DbError = mongodb::error::Error;
 
+const DUPLICATE_KEY_CODE: i32 = 11000;
+const MAX_TRANSACTION_ATTEMPTS: u32 = 5;
+
+pub fn is_duplicate_key_error(e: &DbError) -> bool {
+    match e.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => {
+            write_error.code == DUPLICATE_KEY_CODE
+        }
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY_CODE,
+        _ => false,
+    }
+}
+
 pub async fn connect() -> Database {
     let config = config::get();
 
@@ -17,3 +43,168 @@ pub async fn connect() -> Database {
 
     client.database(&config.mongodb.db_name)
 }
+
+// multi-document transactions are available only on replica sets and sharded clusters
+pub async fn supports_transactions(db: &Database) -> bool {
+    let hello = db.run_command(doc! { "hello": 1 }, None).await;
+
+    match hello {
+        Ok(hello) => hello.contains_key("setName") || hello.get_str("msg") == Ok("isdbgrid"),
+        Err(_) => false,
+    }
+}
+
+// error of transaction body, tells whether the whole transaction is worth retrying
+pub trait TransactionError: From<DbError> {
+    fn is_transient(&self) -> bool;
+}
+
+impl TransactionError for DbError {
+    fn is_transient(&self) -> bool {
+        self.contains_label(TRANSIENT_TRANSACTION_ERROR)
+    }
+}
+
+// runs f in transaction, which is committed when f succeeds and aborted when it fails;
+// transient errors, e.g. write conflict with concurrent transaction, make it start over.
+// f gets context by reference, so its future may borrow from it along with session
+pub async fn with_transaction<C, T, E, F>(client: &Client, context: C, mut f: F) -> Result<T, E>
+where
+    E: TransactionError,
+    F: for<'a> FnMut(&'a mut ClientSession, &'a C) -> LocalBoxFuture<'a, Result<T, E>>,
+{
+    let mut session = client.start_session(None).await?;
+    let mut attempt = 1;
+
+    loop {
+        session.start_transaction(None).await?;
+
+        let result = match f(&mut session, &context).await {
+            Ok(value) => session
+                .commit_transaction()
+                .await
+                .map(|_| value)
+                .map_err(E::from),
+            Err(e) => {
+                if let Err(abort_error) = session.abort_transaction().await {
+                    println!("db: failed to abort transaction: {}", abort_error);
+                }
+
+                Err(e)
+            }
+        };
+
+        match result {
+            Err(e) if e.is_transient() && attempt < MAX_TRANSACTION_ATTEMPTS => attempt += 1,
+            result => return result,
+        }
+    }
+}
+
+// implemented by every stored struct, so its collection and indexes are declared in one place
+pub trait Model {
+    const COLLECTION_NAME: &'static str;
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![]
+    }
+}
+
+fn declared<M: Model>() -> (&'static str, Vec<IndexModel>) {
+    (M::COLLECTION_NAME, M::indexes())
+}
+
+// creates declared indexes missing in db and warns about existing indexes nobody declared,
+// those are never dropped automatically
+pub async fn sync_indexes(db: &Database) {
+    let models = [
+        declared::<User>(),
+        declared::<UserAuth>(),
+        declared::<Post>(),
+        declared::<PostRevision>(),
+        declared::<Comment>(),
+        declared::<Reaction>(),
+        declared::<RefreshToken>(),
+        declared::<RevokedToken>(),
+    ];
+
+    for (collection_name, indexes) in models {
+        let collection = db.collection::<Document>(collection_name);
+
+        // listing fails when collection doesn't exist yet, it has no indexes then
+        let existing_names = collection.list_index_names().await.unwrap_or_default();
+        let declared_names: Vec<String> = indexes.iter().map(index_name).collect();
+
+        for (index, name) in indexes.into_iter().zip(&declared_names) {
+            if existing_names.contains(name) {
+                continue;
+            }
+
+            match collection.create_index(index, None).await {
+                Ok(_) => println!("db: created index \"{}\" on \"{}\"", name, collection_name),
+                Err(e) => {
+                    println!(
+                        "db: failed to create index \"{}\" on \"{}\": {}",
+                        name, collection_name, e
+                    );
+
+                    if is_duplicate_key_error(&e) && collection_name == User::COLLECTION_NAME {
+                        println!(
+                            "db: \"{}\" has duplicates, run `cargo run -- find-duplicate-emails` to list them",
+                            collection_name
+                        );
+                    }
+                }
+            }
+        }
+
+        for name in &existing_names {
+            if name != "_id_" && !declared_names.contains(name) {
+                println!(
+                    "db: unexpected index \"{}\" on \"{}\" is not declared by model",
+                    name, collection_name
+                );
+            }
+        }
+    }
+}
+
+// explicit name or the one mongo generates from keys, e.g. "user_id_1_created_at_-1"
+fn index_name(index: &IndexModel) -> String {
+    if let Some(name) = index.options.as_ref().and_then(|o| o.name.clone()) {
+        return name;
+    }
+
+    index
+        .keys
+        .iter()
+        .map(|(field, value)| match value {
+            Bson::String(kind) => format!("{}_{}", field, kind),
+            value => format!("{}_{}", field, value),
+        })
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+pub fn index(keys: Document) -> IndexModel {
+    IndexModel::builder().keys(keys).build()
+}
+
+pub fn unique_index(keys: Document) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(IndexOptions::builder().unique(true).build())
+        .build()
+}
+
+// documents are removed by mongo as soon as time in the field is passed
+pub fn ttl_index(field: &str) -> IndexModel {
+    IndexModel::builder()
+        .keys(doc! { field: 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build(),
+        )
+        .build()
+}
@@ -0,0 +1,41 @@
+use mongodb::bson::{doc, Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{audit, Timestamp, UserId};
+
+// embedded with #[serde(flatten)] into models which api never deletes for real,
+// soft-deleted documents are hard-deleted by purge job after retention period
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Deletion {
+    pub deleted_at: Option<Timestamp>,
+    pub deleted_by: Option<UserId>,
+}
+
+// `null` also matches documents created before soft delete, which have no such field
+pub fn not_deleted() -> Document {
+    doc! { "deleted_at": Bson::Null }
+}
+
+pub fn deleted() -> Document {
+    doc! { "deleted_at": { "$ne": Bson::Null } }
+}
+
+pub fn mark_deleted(actor: UserId) -> Document {
+    audit::set_with_audit(
+        doc! {
+            "deleted_at": Timestamp::now(),
+            "deleted_by": actor,
+        },
+        Some(actor),
+    )
+}
+
+pub fn mark_restored(actor: UserId) -> Document {
+    audit::set_with_audit(
+        doc! {
+            "deleted_at": Bson::Null,
+            "deleted_by": Bson::Null,
+        },
+        Some(actor),
+    )
+}
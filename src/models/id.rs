@@ -0,0 +1,60 @@
+use std::fmt;
+
+use mongodb::bson::{oid::ObjectId, Bson};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// typed document ids, stored as ObjectId in mongo and exposed as hex string in json;
+// raw bson serializer used by driver is not human-readable, so it keeps ObjectId type,
+// while `bson::to_bson` is human-readable by default and would produce string,
+// so ids are put into queries through `doc!` using From<Id> for Bson
+macro_rules! object_id_type {
+    ($name:ident) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name(ObjectId);
+
+        impl $name {
+            pub fn new() -> Self {
+                $name(ObjectId::new())
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0.to_hex())
+            }
+        }
+
+        impl From<$name> for Bson {
+            fn from(id: $name) -> Self {
+                Bson::ObjectId(id.0)
+            }
+        }
+
+        impl From<$name> for ObjectId {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&self.0.to_hex())
+                } else {
+                    self.0.serialize(serializer)
+                }
+            }
+        }
+
+        // accepts both ObjectId and hex string, malformed hex is rejected
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                ObjectId::deserialize(deserializer).map($name)
+            }
+        }
+    };
+}
+
+object_id_type!(UserId);
+object_id_type!(PostId);
+object_id_type!(CommentId);
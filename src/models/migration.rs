@@ -0,0 +1,19 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+// record of migration applied to database, _id is migration version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub _id: i64,
+    pub name: String,
+    pub applied_at: DateTime,
+}
+
+// only one instance may run migrations at a time,
+// lock is considered abandoned after locked_until, so crashed instance can't block others forever
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MigrationLock {
+    pub _id: String,
+    pub owner: String,
+    pub locked_until: DateTime,
+}
@@ -1,8 +1,25 @@
+pub mod audit;
+pub mod comment;
 pub mod db;
+pub mod deletion;
+pub mod id;
+pub mod migration;
 pub mod post;
+pub mod reaction;
+pub mod revision;
+pub mod timestamp;
+pub mod token;
 pub mod user;
 
+pub use audit::Audit;
+pub use comment::Comment;
 pub use db::DbError;
+pub use deletion::Deletion;
+pub use id::{CommentId, PostId, UserId};
 pub use mongodb::Database;
 pub use post::Post;
+pub use reaction::Reaction;
+pub use revision::PostRevision;
+pub use timestamp::Timestamp;
+pub use token::{RefreshToken, RevokedToken};
 pub use user::User;
@@ -1,10 +1,132 @@
-
+use mongodb::{
+    bson::{doc, Bson, Document},
+    options::IndexOptions,
+    IndexModel,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::models::{
+    db::{self, Model},
+    Audit, Deletion, PostId, Timestamp, User, UserId,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStatus {
+    // visible only to author
+    Draft,
+    #[default]
+    Published,
+    // becomes visible to everyone at publish_at
+    Scheduled,
+    // hidden from everyone but author, kept for history
+    Archived,
+}
+
+impl From<PostStatus> for Bson {
+    fn from(status: PostStatus) -> Self {
+        let status = match status {
+            PostStatus::Draft => "draft",
+            PostStatus::Published => "published",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::Archived => "archived",
+        };
+
+        Bson::String(status.to_string())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Post {
-    pub _id: String,
+    pub _id: PostId,
     pub title: String,
     pub content: String,
-    pub user_id: String,
+    // normalized with normalize_tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub user_id: UserId,
+    // posts created before statuses existed were all public
+    #[serde(default)]
+    pub status: PostStatus,
+    // time post became or will become visible, not set for drafts
+    pub publish_at: Option<Timestamp>,
+    // number of the latest PostRevision, changed with every edit of title or content
+    #[serde(default)]
+    pub revision: i64,
+    // number of not deleted comments, maintained by CommentService;
+    // posts created before comments existed have no such field
+    #[serde(default)]
+    pub comment_count: i64,
+    // number of likes, maintained by ReactionService;
+    // posts created before likes existed have no such field
+    #[serde(default)]
+    pub like_count: i64,
+    #[serde(flatten)]
+    pub audit: Audit,
+    #[serde(flatten)]
+    pub deletion: Deletion,
+}
+
+impl Model for Post {
+    const COLLECTION_NAME: &'static str = "posts";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![
+            db::index(doc! { "user_id": 1 }),
+            db::index(doc! { "deleted_at": 1 }),
+            db::index(doc! { "status": 1, "publish_at": 1 }),
+            // multikey, as tags is an array
+            db::index(doc! { "tags": 1 }),
+            // matches in title are ranked higher
+            IndexModel::builder()
+                .keys(doc! { "title": "text", "content": "text" })
+                .options(
+                    IndexOptions::builder()
+                        .weights(doc! { "title": 3, "content": 1 })
+                        .build(),
+                )
+                .build(),
+        ]
+    }
+}
+
+// "#Rust  Lang" and "rust-lang" are the same tag
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join("-")
+        .to_lowercase()
+}
+
+// empty and repeated tags are dropped, order of the rest is kept
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+
+    for tag in tags.iter().map(|tag| normalize_tag(tag)) {
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    normalized
+}
+
+// scheduled post is visible as soon as its time comes, even before job marks it published
+pub fn published() -> Document {
+    doc! {
+        "$or": [
+            { "status": PostStatus::Published },
+            { "status": PostStatus::Scheduled, "publish_at": { "$lte": Timestamp::now() } },
+        ]
+    }
+}
+
+// author also sees own drafts, scheduled and archived posts
+pub fn visible_to(viewer: Option<&User>) -> Document {
+    match viewer {
+        Some(viewer) => doc! { "$or": [published(), { "user_id": viewer._id }] },
+        None => published(),
+    }
 }
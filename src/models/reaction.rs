@@ -0,0 +1,31 @@
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    db::{self, Model},
+    PostId, Timestamp, UserId,
+};
+
+// like given by user to post, never changed after creation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reaction {
+    pub _id: ObjectId,
+    pub post_id: PostId,
+    pub user_id: UserId,
+    pub created_at: Timestamp,
+}
+
+impl Model for Reaction {
+    const COLLECTION_NAME: &'static str = "reactions";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![
+            // user can like post only once, also serves lookups of likes of listed posts
+            db::unique_index(doc! { "user_id": 1, "post_id": 1 }),
+            db::index(doc! { "post_id": 1 }),
+        ]
+    }
+}
@@ -0,0 +1,31 @@
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    db::{self, Model},
+    PostId, Timestamp, UserId,
+};
+
+// snapshot of post content after each change, never updated or removed while post exists
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostRevision {
+    pub _id: ObjectId,
+    pub post_id: PostId,
+    // sequential number within post, starting from 1 for the created post
+    pub rev: i64,
+    pub title: String,
+    pub content: String,
+    pub created_at: Timestamp,
+    pub created_by: Option<UserId>,
+}
+
+impl Model for PostRevision {
+    const COLLECTION_NAME: &'static str = "post_revisions";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![db::unique_index(doc! { "post_id": 1, "rev": 1 })]
+    }
+}
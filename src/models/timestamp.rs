@@ -0,0 +1,46 @@
+use mongodb::bson::{Bson, DateTime};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+// stored as BSON date in mongo and exposed as RFC 3339 string in json and query strings,
+// same serializer rules as for typed ids apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(DateTime);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Timestamp(DateTime::now())
+    }
+}
+
+impl From<Timestamp> for Bson {
+    fn from(timestamp: Timestamp) -> Self {
+        Bson::DateTime(timestamp.0)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return self.0.serialize(serializer);
+        }
+
+        match self.0.try_to_rfc3339_string() {
+            Ok(time) => serializer.serialize_str(&time),
+            Err(e) => Err(serde::ser::Error::custom(e)),
+        }
+    }
+}
+
+// serde reports buffered content of #[serde(flatten)] fields as human-readable,
+// so BSON date can come here in both modes and is accepted along with RFC 3339 string
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Bson::deserialize(deserializer)? {
+            Bson::DateTime(time) => Ok(Timestamp(time)),
+            Bson::String(time) => DateTime::parse_rfc3339_str(&time)
+                .map(Timestamp)
+                .map_err(|_| D::Error::custom("expected RFC 3339 date")),
+            _ => Err(D::Error::custom("expected date")),
+        }
+    }
+}
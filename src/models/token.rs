@@ -0,0 +1,58 @@
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    db::{self, Model},
+    UserId,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub _id: ObjectId,
+    pub user_id: UserId,
+    // all tokens produced by rotating the same login share family_id
+    pub family_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime,
+    pub revoked: bool,
+}
+
+impl Model for RefreshToken {
+    const COLLECTION_NAME: &'static str = "refresh_tokens";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![
+            db::ttl_index("expires_at"),
+            db::index(doc! { "token_hash": 1 }),
+            db::index(doc! { "family_id": 1 }),
+            db::index(doc! { "user_id": 1 }),
+        ]
+    }
+}
+
+// access tokens can't be invalidated by themselves, so revoked ones are listed until they expire
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevokedToken {
+    pub _id: ObjectId,
+    pub user_id: UserId,
+    // revokes single access token
+    pub jti: Option<String>,
+    // revokes every access token of user issued before this unix time in milliseconds
+    pub issued_before: Option<i64>,
+    pub expires_at: DateTime,
+}
+
+impl Model for RevokedToken {
+    const COLLECTION_NAME: &'static str = "revoked_tokens";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![
+            db::ttl_index("expires_at"),
+            db::index(doc! { "jti": 1 }),
+            db::index(doc! { "user_id": 1 }),
+        ]
+    }
+}
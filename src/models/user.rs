@@ -1,31 +1,96 @@
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson},
+    options::{Collation, CollationStrength, IndexOptions},
+    IndexModel,
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+use crate::models::{
+    db::{self, Model},
+    Audit, Deletion, UserId,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum Role {
     User,
     Admin,
 }
 
+impl From<Role> for Bson {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::User => Bson::String("User".to_string()),
+            Role::Admin => Bson::String("Admin".to_string()),
+        }
+    }
+}
+
 impl Role {
     pub fn satisfy(&self, another: Role) -> bool {
         if another == Role::Admin {
             return *self == Role::Admin;
         }
 
-        return true;
+        true
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
-    pub _id: String,
+    pub _id: UserId,
     pub role: Role,
     pub email: String,
+    #[serde(flatten)]
+    pub audit: Audit,
+    #[serde(flatten)]
+    pub deletion: Deletion,
+}
+
+impl Model for User {
+    const COLLECTION_NAME: &'static str = "users";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![
+            IndexModel::builder()
+                .keys(doc! { "email": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .collation(email_collation())
+                        .build(),
+                )
+                .build(),
+            db::index(doc! { "deleted_at": 1 }),
+        ]
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserAuth {
-    pub _id: String,
-    pub user_id: String,
+    pub _id: ObjectId,
+    pub user_id: UserId,
     pub password_hash: String,
+    #[serde(flatten)]
+    pub audit: Audit,
+}
+
+impl Model for UserAuth {
+    const COLLECTION_NAME: &'static str = "user_auths";
+
+    fn indexes() -> Vec<IndexModel> {
+        vec![db::unique_index(doc! { "user_id": 1 })]
+    }
+}
+
+// emails are stored normalized, but are compared case-insensitively
+// to also match records created before normalization
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+pub fn email_collation() -> Collation {
+    Collation::builder()
+        .locale("en")
+        .strength(CollationStrength::Secondary)
+        .build()
 }
@@ -0,0 +1,85 @@
+use actix_web::{delete, get, patch, post, web, HttpResponse, Scope};
+
+use crate::{
+    extractors::{AuthUser, MaybeUser, ValidatedJson},
+    models::{CommentId, PostId},
+    services::comment::{CommentListQuery, CreateCommentData, UpdateCommentData},
+    utils::errors::ApiError,
+    AppState,
+};
+
+#[get("")]
+async fn get_comments(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+    query: web::Query<CommentListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let comments = state
+        .i
+        .comment_service()
+        .list(&path, query.into_inner(), user.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(comments))
+}
+
+#[post("")]
+async fn create_comment(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+    comment_data: ValidatedJson<CreateCommentData>,
+) -> Result<HttpResponse, ApiError> {
+    let comment = state
+        .i
+        .comment_service()
+        .create(&path, comment_data.into_inner(), &user)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(comment))
+}
+
+#[patch("/{comment_id}")]
+async fn update_comment(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<(PostId, CommentId)>,
+    comment_data: ValidatedJson<UpdateCommentData>,
+) -> Result<HttpResponse, ApiError> {
+    let (post_id, comment_id) = path.into_inner();
+
+    let comment = state
+        .i
+        .comment_service()
+        .update(&post_id, &comment_id, comment_data.into_inner(), &user)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(comment))
+}
+
+#[delete("/{comment_id}")]
+async fn delete_comment(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<(PostId, CommentId)>,
+) -> Result<HttpResponse, ApiError> {
+    let (post_id, comment_id) = path.into_inner();
+
+    state
+        .i
+        .comment_service()
+        .delete(&post_id, &comment_id, &user)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// nested into /posts scope
+pub fn scope() -> Scope {
+    web::scope("/{post_id}/comments")
+        .service(get_comments)
+        .service(create_comment)
+        .service(update_comment)
+        .service(delete_comment)
+}
@@ -1,3 +1,13 @@
+use crate::utils::errors::ApiError;
+
+pub mod comments;
 pub mod posts;
+pub mod revisions;
 pub mod status;
+pub mod tags;
 pub mod users;
+pub mod well_known;
+
+pub async fn not_found() -> Result<actix_web::HttpResponse, ApiError> {
+    Err(ApiError::NotFound)
+}
@@ -1,51 +1,169 @@
-use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Scope};
+use actix_web::{delete, get, patch, post, put, web, HttpResponse, Scope};
 
-use crate::{services::post::CreatePostData, AppState};
+use crate::{
+    extractors::{AdminUser, AuthUser, MaybeUser, ValidatedJson},
+    models::PostId,
+    routes::{comments, revisions},
+    services::post::{CreatePostData, PostListQuery, PostSearchQuery, UpdatePostData},
+    utils::errors::ApiError,
+    AppState,
+};
 
 #[get("")]
-async fn get_all_posts(state: web::Data<AppState>) -> impl Responder {
-    let result = state.i.post_service().list().await;
+async fn get_all_posts(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    query: web::Query<PostListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let posts = state
+        .i
+        .post_service()
+        .list(query.into_inner(), user.as_ref())
+        .await?;
+    let posts = state
+        .i
+        .reaction_service()
+        .mark_liked(posts, user.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(posts))
+}
+
+#[get("/search")]
+async fn search_posts(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    query: web::Query<PostSearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let results = state
+        .i
+        .post_service()
+        .search(query.into_inner(), user.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(results))
+}
+
+#[get("/deleted")]
+async fn get_deleted_posts(
+    _admin: AdminUser,
+    state: web::Data<AppState>,
+    query: web::Query<PostListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let posts = state
+        .i
+        .post_service()
+        .list_deleted(query.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(posts))
+}
+
+#[post("/{id}/restore")]
+async fn restore_post(
+    AdminUser(admin): AdminUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+) -> Result<HttpResponse, ApiError> {
+    let post = state.i.post_service().restore(&path, &admin).await?;
+
+    Ok(HttpResponse::Ok().json(post))
+}
+
+#[put("/{id}/like")]
+async fn like_post(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+) -> Result<HttpResponse, ApiError> {
+    let like_state = state.i.reaction_service().like(&path, &user).await?;
+
+    Ok(HttpResponse::Ok().json(like_state))
+}
 
-    match result {
-        Ok(posts) => return HttpResponse::Ok().json(posts),
-        _ => return HttpResponse::InternalServerError().finish(),
-    }
+#[delete("/{id}/like")]
+async fn unlike_post(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+) -> Result<HttpResponse, ApiError> {
+    let like_state = state.i.reaction_service().unlike(&path, &user).await?;
+
+    Ok(HttpResponse::Ok().json(like_state))
 }
 
 #[get("/{id}")]
-async fn get_post_by_id(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
-    let result = state.i.post_service().get_by_id(&path).await;
-    HttpResponse::Ok().json(result)
+async fn get_post_by_id(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(post) = state
+        .i
+        .post_service()
+        .get_visible(&path, user.as_ref())
+        .await?
+    else {
+        return Err(ApiError::NotFound);
+    };
+
+    Ok(HttpResponse::Ok().json(post))
 }
 
 #[post("")]
 async fn create_post(
-    req: HttpRequest,
+    AuthUser(user): AuthUser,
     state: web::Data<AppState>,
-    post_data: web::Json<CreatePostData>,
-) -> impl Responder {
-    let user = state.i.user_service().get_user_from_req(&req).await;
+    post_data: ValidatedJson<CreatePostData>,
+) -> Result<HttpResponse, ApiError> {
+    let post = state
+        .i
+        .post_service()
+        .create(post_data.into_inner(), &user._id)
+        .await?;
 
-    let Ok(user) = user else {
-        return state.format_err(user.unwrap_err());
-    };
+    Ok(HttpResponse::Ok().json(post))
+}
 
-    let result = state
+#[patch("/{id}")]
+async fn update_post(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+    post_data: ValidatedJson<UpdatePostData>,
+) -> Result<HttpResponse, ApiError> {
+    let post = state
         .i
         .post_service()
-        .create(post_data.into_inner(), &user._id)
-        .await;
-    match result {
-        Ok(insert_info) => return HttpResponse::Ok().json(insert_info),
-        _ => return HttpResponse::InternalServerError().finish(),
-    }
+        .update(&path, post_data.into_inner(), &user)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(post))
+}
+
+#[delete("/{id}")]
+async fn delete_post(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+) -> Result<HttpResponse, ApiError> {
+    state.i.post_service().delete(&path, &user).await?;
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
 pub fn scope() -> Scope {
-    let scope = web::scope("/posts")
+    web::scope("/posts")
         .service(get_all_posts)
         .service(create_post)
-        .service(get_post_by_id);
-
-    scope
+        .service(search_posts)
+        .service(get_deleted_posts)
+        .service(get_post_by_id)
+        .service(update_post)
+        .service(delete_post)
+        .service(restore_post)
+        .service(like_post)
+        .service(unlike_post)
+        .service(comments::scope())
+        .service(revisions::scope())
 }
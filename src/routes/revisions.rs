@@ -0,0 +1,84 @@
+use actix_web::{get, post, web, HttpResponse, Scope};
+
+use crate::{
+    extractors::{AuthUser, MaybeUser},
+    models::PostId,
+    services::post::{RevisionDiffQuery, RevisionListQuery},
+    utils::errors::ApiError,
+    AppState,
+};
+
+#[get("")]
+async fn get_revisions(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+    query: web::Query<RevisionListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let revisions = state
+        .i
+        .post_service()
+        .list_revisions(&path, query.into_inner(), user.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+#[get("/diff")]
+async fn get_revision_diff(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    path: web::Path<PostId>,
+    query: web::Query<RevisionDiffQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let revision_diff = state
+        .i
+        .post_service()
+        .diff_revisions(&path, query.into_inner(), user.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(revision_diff))
+}
+
+#[get("/{rev}")]
+async fn get_revision(
+    MaybeUser(user): MaybeUser,
+    state: web::Data<AppState>,
+    path: web::Path<(PostId, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (post_id, rev) = path.into_inner();
+
+    let revision = state
+        .i
+        .post_service()
+        .get_revision(&post_id, rev, user.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+#[post("/{rev}/restore")]
+async fn restore_revision(
+    AuthUser(user): AuthUser,
+    state: web::Data<AppState>,
+    path: web::Path<(PostId, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (post_id, rev) = path.into_inner();
+
+    let post = state
+        .i
+        .post_service()
+        .restore_revision(&post_id, rev, &user)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(post))
+}
+
+// nested into /posts scope
+pub fn scope() -> Scope {
+    web::scope("/{post_id}/revisions")
+        .service(get_revisions)
+        .service(get_revision_diff)
+        .service(get_revision)
+        .service(restore_revision)
+}
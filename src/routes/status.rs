@@ -14,7 +14,5 @@ async fn get_status() -> impl Responder {
 }
 
 pub fn scope() -> Scope {
-    let scope = web::scope("/status").service(get_status);
-
-    scope
+    web::scope("/status").service(get_status)
 }
@@ -0,0 +1,21 @@
+use actix_web::{get, web, HttpResponse, Scope};
+
+use crate::{services::post::TagListQuery, utils::errors::ApiError, AppState};
+
+#[get("")]
+async fn get_tags(
+    state: web::Data<AppState>,
+    query: web::Query<TagListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let tags = state
+        .i
+        .post_service()
+        .tag_counts(query.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+pub fn scope() -> Scope {
+    web::scope("/tags").service(get_tags)
+}
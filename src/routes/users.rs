@@ -1,128 +1,185 @@
-use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Scope};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Scope};
+use actix_web_lab::middleware::from_fn;
 use serde::Serialize;
 
 use crate::{
-    models::{user::Role, User},
-    services::user::CreateUserData,
-    utils::errors,
+    extractors::{AdminUser, AuthUser, ValidatedJson},
+    middleware::auth::require_admin,
+    models::{User, UserId},
+    services::{
+        token::{LogoutData, RefreshTokenData},
+        user::{CreateUserData, LoginUserData, UserListQuery},
+    },
+    utils::errors::ApiError,
     AppState,
 };
 
-#[get("")]
-async fn get_all_users(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
-    let check_role_result = state
+#[get("", wrap = "from_fn(require_admin)")]
+async fn get_all_users(
+    state: web::Data<AppState>,
+    query: web::Query<UserListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let users = state.i.user_service().list(query.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+#[get("/deleted", wrap = "from_fn(require_admin)")]
+async fn get_deleted_users(
+    state: web::Data<AppState>,
+    query: web::Query<UserListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let users = state
         .i
         .user_service()
-        .get_user_from_req_with_role(&req, Role::Admin)
-        .await;
+        .list_deleted(query.into_inner())
+        .await?;
 
-    if check_role_result.is_err() {
-        return state.format_err(check_role_result.unwrap_err());
-    }
+    Ok(HttpResponse::Ok().json(users))
+}
 
-    let result = state.i.user_service().list().await;
+#[delete("/{id}", wrap = "from_fn(require_admin)")]
+async fn delete_user(
+    AdminUser(admin): AdminUser,
+    state: web::Data<AppState>,
+    path: web::Path<UserId>,
+) -> Result<HttpResponse, ApiError> {
+    state.i.user_service().delete(&path, &admin).await?;
 
-    match result {
-        Ok(posts) => return HttpResponse::Ok().json(posts),
-        _ => return HttpResponse::InternalServerError().finish(),
-    }
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[get("/{id}")]
-async fn get_user_by_id(
-    req: HttpRequest,
+#[post("/{id}/restore", wrap = "from_fn(require_admin)")]
+async fn restore_user(
+    AdminUser(admin): AdminUser,
     state: web::Data<AppState>,
-    path: web::Path<(String,)>,
-) -> impl Responder {
-    let check_role_result = state
-        .i
-        .user_service()
-        .get_user_from_req_with_role(&req, Role::Admin)
-        .await;
+    path: web::Path<UserId>,
+) -> Result<HttpResponse, ApiError> {
+    let user = state.i.user_service().restore(&path, &admin).await?;
 
-    if check_role_result.is_err() {
-        return state.format_err(check_role_result.unwrap_err());
-    }
-
-    let result = state.i.user_service().get_by_id(&path.into_inner().0).await;
-    HttpResponse::Ok().json(result)
+    Ok(HttpResponse::Ok().json(user))
 }
 
-#[get("/me")]
-async fn get_me(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
-    let user = state.i.user_service().get_user_from_req(&req).await;
+#[get("/{id}", wrap = "from_fn(require_admin)")]
+async fn get_user_by_id(
+    state: web::Data<AppState>,
+    path: web::Path<UserId>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(user) = state.i.user_service().get_by_id(&path).await? else {
+        return Err(ApiError::NotFound);
+    };
 
-    if user.is_err() {
-        return state.format_err(user.unwrap_err());
-    }
+    Ok(HttpResponse::Ok().json(user))
+}
 
-    return HttpResponse::Ok().json(user.unwrap());
+#[get("/me")]
+async fn get_me(AuthUser(user): AuthUser) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(user))
 }
 
 #[derive(Serialize)]
 struct CreateUserResponse {
     user: User,
     token: String,
+    refresh_token: String,
 }
 
 #[post("/register")]
 async fn create_user(
     state: web::Data<AppState>,
-    user_data: web::Json<CreateUserData>,
-) -> impl Responder {
-    let result = state.i.user_service().create(user_data.into_inner()).await;
-
-    if result.is_err() {
-        return state.format_err(result.unwrap_err());
-    }
-    let user = result.unwrap();
-
-    let token = state.i.auth_service().create_token(&user);
+    user_data: ValidatedJson<CreateUserData>,
+) -> Result<HttpResponse, ApiError> {
+    let user = state
+        .i
+        .user_service()
+        .create(user_data.into_inner())
+        .await?;
 
-    if token.is_err() {
-        return state.format_err(token.unwrap_err());
-    }
+    let tokens = state.i.token_service().issue_tokens(&user).await?;
 
-    return HttpResponse::Ok().json(CreateUserResponse {
-        token: token.unwrap(),
+    Ok(HttpResponse::Ok().json(CreateUserResponse {
+        token: tokens.token,
+        refresh_token: tokens.refresh_token,
         user,
-    });
+    }))
 }
 
 #[post("/login")]
 async fn login_user(
     state: web::Data<AppState>,
-    user_data: web::Json<CreateUserData>,
-) -> impl Responder {
-    let user = state
+    user_data: web::Json<LoginUserData>,
+) -> Result<HttpResponse, ApiError> {
+    let user = state.i.user_service().login(user_data.into_inner()).await?;
+
+    let tokens = state.i.token_service().issue_tokens(&user).await?;
+
+    Ok(HttpResponse::Ok().json(CreateUserResponse {
+        token: tokens.token,
+        refresh_token: tokens.refresh_token,
+        user,
+    }))
+}
+
+#[post("/token/refresh")]
+async fn refresh_token(
+    state: web::Data<AppState>,
+    token_data: web::Json<RefreshTokenData>,
+) -> Result<HttpResponse, ApiError> {
+    let used_token = state
         .i
-        .user_service()
-        .get_by_email(&user_data.into_inner().email)
-        .await;
+        .token_service()
+        .use_refresh_token(&token_data.refresh_token)
+        .await?;
 
-    let Some(user) = user else {
-        return state.format_err(errors::build_generic_err());
+    let Some(user) = state
+        .i
+        .user_service()
+        .get_by_id(&used_token.user_id)
+        .await?
+    else {
+        return Err(ApiError::Unauthorized);
     };
 
-    let token = state.i.auth_service().create_token(&user);
+    let tokens = state
+        .i
+        .token_service()
+        .issue_rotated_tokens(&user, &used_token)
+        .await?;
 
-    if token.is_err() {
-        return state.format_err(token.unwrap_err());
-    }
+    Ok(HttpResponse::Ok().json(tokens))
+}
 
-    return HttpResponse::Ok().json(CreateUserResponse {
-        token: token.unwrap(),
-        user,
-    });
+#[post("/logout")]
+async fn logout_user(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let claims = state.i.user_service().get_claims_from_req(&req).await?;
+
+    // only empty body means no options, malformed one is rejected instead of being ignored
+    let logout_data = if body.is_empty() {
+        LogoutData::default()
+    } else {
+        serde_json::from_slice::<LogoutData>(&body)
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    };
+
+    state.i.token_service().logout(&claims, logout_data).await?;
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
 pub fn scope() -> Scope {
-    let scope = web::scope("/users")
-        .service(get_all_users)
+    web::scope("/users")
         .service(create_user)
+        .service(login_user)
+        .service(refresh_token)
+        .service(logout_user)
         .service(get_me)
+        .service(get_all_users)
+        .service(get_deleted_users)
         .service(get_user_by_id)
-        .service(login_user);
-
-    scope
+        .service(delete_user)
+        .service(restore_user)
 }
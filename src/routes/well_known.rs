@@ -0,0 +1,12 @@
+use actix_web::{get, web, HttpResponse, Responder, Scope};
+
+use crate::AppState;
+
+#[get("/jwks.json")]
+async fn get_jwks(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(state.i.auth_service().jwks())
+}
+
+pub fn scope() -> Scope {
+    web::scope("/.well-known").service(get_jwks)
+}
@@ -1,30 +1,58 @@
-
-use crate::models::User;
-use crate::{config, utils::errors};
-
+use crate::models::{User, UserId};
+use crate::services::jwt_keys::JwtKeys;
+use crate::{config, utils::errors::ApiError};
+
+use actix_web::web;
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2, Params,
+};
 use jsonwebtoken::{
-    decode, encode, errors::Error, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+    decode, decode_header, encode,
+    errors::{Error, ErrorKind},
+    jwk::JwkSet,
+    Header, Validation,
 };
+use mongodb::bson::oid::ObjectId;
+use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 
 use std::time;
 
 #[derive(Debug)]
-pub struct AuthService {}
+pub struct AuthService {
+    keys: JwtKeys,
+    // verified against when user is not found, so response time doesn't reveal existing emails
+    dummy_hash: String,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
-    pub user_id: String,
+    pub user_id: UserId,
+    pub jti: String,
+    pub iat: usize,
+    // iat has only second precision, so revocation compares against this one,
+    // tokens issued before it was added are treated as issued at the epoch
+    #[serde(default)]
+    pub iat_ms: usize,
     pub exp: usize,
 }
 
-impl AuthService {
-    pub fn create_token(&self, user: &User) -> Result<String, errors::Error> {
-        let key = config::get().api.jwt_secret.as_bytes();
+#[derive(Debug, PartialEq)]
+pub enum PasswordCheck {
+    Valid,
+    // password is correct, but stored hash is legacy or uses outdated params
+    ValidNeedsRehash,
+    Invalid,
+}
 
+impl AuthService {
+    pub fn create_token(&self, user: &User) -> Result<String, ApiError> {
         let header = Header {
-            alg: Algorithm::HS512,
+            alg: self.keys.algorithm,
+            kid: self.keys.key_id.clone(),
             ..Default::default()
         };
 
@@ -33,44 +61,131 @@ impl AuthService {
             .expect("How the fuck i am tired of impossible errors");
 
         let claim = Claims {
-            user_id: user._id.clone(),
-            exp: (time.as_secs() + 365 * 24 * 60 * 60) as usize,
+            user_id: user._id,
+            jti: ObjectId::new().to_hex(),
+            iat: time.as_secs() as usize,
+            iat_ms: time.as_millis() as usize,
+            exp: (time.as_secs() + config::get().api.access_token_ttl) as usize,
         };
 
-        let token = encode(&header, &claim, &EncodingKey::from_secret(key));
+        let token = encode(&header, &claim, &self.keys.encoding_key);
 
         match token {
-            Ok(t) => return Ok(t),
-            Err(_) => return Err(errors::build_generic_err()),
+            Ok(t) => Ok(t),
+            Err(_) => Err(ApiError::Internal),
         }
     }
 
     pub fn decode_token(&self, token_str: &str) -> Result<Claims, Error> {
-        let key = config::get().api.jwt_secret.as_bytes();
-        let claims = match decode::<Claims>(
-            token_str,
-            &DecodingKey::from_secret(key),
-            &Validation::new(Algorithm::HS512),
-        ) {
-            Ok(c) => c.claims,
-            Err(e) => return Err(e),
+        let header = decode_header(token_str)?;
+
+        // kid selects one of currently accepted keys, so tokens signed before rotation stay valid
+        let Some(key) = self.keys.decoding_key(header.kid.as_deref()) else {
+            return Err(ErrorKind::InvalidSignature.into());
         };
 
-        return Ok(claims);
+        let token_data = decode::<Claims>(token_str, key, &Validation::new(self.keys.algorithm))?;
+
+        Ok(token_data.claims)
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        self.keys.jwks()
     }
 
-    pub fn generate_hash(&self, string: &str) -> String {
-        let mut hasher = Sha256::new();
+    // argon2 is deliberately slow, so hashing runs on blocking thread pool instead of stalling workers
+    pub async fn hash_password(&self, password: &str) -> Result<String, ApiError> {
+        let password = password.to_string();
 
-        hasher.update(string.as_bytes());
-        hasher.update(config::get().api.hash_salt.as_bytes());
+        match web::block(move || hash_password(&password)).await {
+            Ok(Ok(hash)) => Ok(hash),
+            _ => Err(ApiError::Internal),
+        }
+    }
 
-        let hash = hasher.finalize();
+    pub async fn verify_password(&self, password: &str, password_hash: &str) -> PasswordCheck {
+        let password = password.to_string();
+        let password_hash = password_hash.to_string();
+
+        web::block(move || verify_password(&password, &password_hash))
+            .await
+            .unwrap_or(PasswordCheck::Invalid)
+    }
 
-        format!("{:x}", hash)
+    pub async fn verify_dummy_password(&self, password: &str) {
+        self.verify_password(password, &self.dummy_hash).await;
     }
 
     pub fn new() -> Self {
-        AuthService {}
+        let dummy_hash =
+            hash_password("dummy-password").expect("Failed to create dummy password hash");
+
+        AuthService {
+            keys: JwtKeys::from_config(),
+            dummy_hash,
+        }
+    }
+}
+
+fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+fn verify_password(password: &str, password_hash: &str) -> PasswordCheck {
+    // hashes created before argon2 are plain hex-encoded salted sha256
+    if !password_hash.starts_with('$') {
+        let legacy_hash = generate_legacy_hash(password);
+
+        if bool::from(legacy_hash.as_bytes().ct_eq(password_hash.as_bytes())) {
+            return PasswordCheck::ValidNeedsRehash;
+        }
+
+        return PasswordCheck::Invalid;
+    }
+
+    let Ok(parsed_hash) = PasswordHash::new(password_hash) else {
+        return PasswordCheck::Invalid;
+    };
+
+    if Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_err()
+    {
+        return PasswordCheck::Invalid;
+    }
+
+    let is_current = parsed_hash.algorithm == argon2::ARGON2ID_IDENT
+        && Params::try_from(&parsed_hash).is_ok_and(|params| is_current_params(&params));
+
+    if is_current {
+        PasswordCheck::Valid
+    } else {
+        PasswordCheck::ValidNeedsRehash
     }
 }
+
+// params parsed from hash always carry output length, while defaults leave it unset
+fn is_current_params(params: &Params) -> bool {
+    let current = Params::default();
+
+    params.m_cost() == current.m_cost()
+        && params.t_cost() == current.t_cost()
+        && params.p_cost() == current.p_cost()
+        && params.output_len().unwrap_or(Params::DEFAULT_OUTPUT_LEN)
+            == current.output_len().unwrap_or(Params::DEFAULT_OUTPUT_LEN)
+}
+
+fn generate_legacy_hash(string: &str) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(string.as_bytes());
+    hasher.update(config::get().api.hash_salt.as_bytes());
+
+    let hash = hasher.finalize();
+
+    format!("{:x}", hash)
+}
@@ -0,0 +1,286 @@
+use std::rc::Rc;
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+    Collection,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::{
+    models::{
+        audit,
+        comment::{self, Comment},
+        db::Model,
+        deletion,
+        user::Role,
+        Audit, CommentId, Database, Deletion, Post, PostId, User,
+    },
+    services::PostService,
+    utils::{
+        errors::ApiError,
+        pagination::{self, Page, PageRequest, SortOrder},
+        trim,
+    },
+};
+
+#[derive(Debug)]
+#[allow(unused)]
+pub struct CommentService {
+    db: Rc<Database>,
+    post_service: Rc<PostService>,
+    collection: Collection<Comment>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCommentData {
+    #[serde(deserialize_with = "trim::trimmed")]
+    #[validate(length(
+        min = 1,
+        max = 5000,
+        message = "Content should be 1-5000 characters long"
+    ))]
+    pub content: String,
+    // comment being replied to, top-level comment is created when not set
+    pub parent_id: Option<CommentId>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCommentData {
+    #[serde(deserialize_with = "trim::trimmed")]
+    #[validate(length(
+        min = 1,
+        max = 5000,
+        message = "Content should be 1-5000 characters long"
+    ))]
+    pub content: String,
+}
+
+// one level of thread is listed at a time: top-level comments, or replies to `parent`
+#[derive(Debug, Deserialize)]
+pub struct CommentListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<SortOrder>,
+    pub parent: Option<CommentId>,
+}
+
+impl CommentService {
+    // comments are read in order they were written unless asked otherwise
+    pub async fn list(
+        &self,
+        post_id: &PostId,
+        query: CommentListQuery,
+        viewer: Option<&User>,
+    ) -> Result<Page<Comment>, ApiError> {
+        self.get_post(post_id, viewer).await?;
+
+        let sort = query.sort.unwrap_or(SortOrder::Oldest);
+        let page = PageRequest::new(query.limit, query.cursor.as_deref(), Some(sort))?;
+
+        let parent_id = match query.parent {
+            Some(parent) => Bson::from(parent),
+            None => Bson::Null,
+        };
+
+        let mut conditions = vec![
+            deletion::not_deleted(),
+            doc! { "post_id": post_id, "parent_id": parent_id },
+        ];
+        conditions.extend(page.cursor_condition());
+
+        let comments: Vec<Comment> = self
+            .collection
+            .find(pagination::and_filter(conditions), page.find_options())
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(page.into_page(comments, |comment| comment._id.into()))
+    }
+
+    pub async fn create(
+        &self,
+        post_id: &PostId,
+        comment_data: CreateCommentData,
+        user: &User,
+    ) -> Result<Comment, ApiError> {
+        self.get_post(post_id, Some(user)).await?;
+
+        let ancestors = match comment_data.parent_id {
+            Some(parent_id) => {
+                let Some(parent) = self.get_by_id(post_id, &parent_id).await? else {
+                    return Err(ApiError::BadRequest(
+                        "Parent comment is not found".to_string(),
+                    ));
+                };
+
+                if parent.ancestors.len() + 1 >= comment::MAX_DEPTH {
+                    return Err(ApiError::BadRequest(format!(
+                        "Replies can't be nested deeper than {} levels",
+                        comment::MAX_DEPTH
+                    )));
+                }
+
+                let mut ancestors = parent.ancestors;
+                ancestors.push(parent._id);
+                ancestors
+            }
+            None => vec![],
+        };
+
+        let new_comment = Comment {
+            _id: CommentId::new(),
+            post_id: *post_id,
+            user_id: user._id,
+            parent_id: comment_data.parent_id,
+            ancestors,
+            content: comment_data.content,
+            reply_count: 0,
+            audit: Audit::new(Some(user._id)),
+            deletion: Deletion::default(),
+        };
+
+        self.collection.insert_one(&new_comment, None).await?;
+
+        if let Some(parent_id) = new_comment.parent_id {
+            self.change_reply_count(&parent_id, 1).await?;
+        }
+
+        self.post_service.change_comment_count(post_id, 1).await?;
+
+        Ok(new_comment)
+    }
+
+    // only author can change what was written on their behalf
+    pub async fn update(
+        &self,
+        post_id: &PostId,
+        id: &CommentId,
+        comment_data: UpdateCommentData,
+        user: &User,
+    ) -> Result<Comment, ApiError> {
+        let Some(comment) = self.get_by_id(post_id, id).await? else {
+            return Err(ApiError::NotFound);
+        };
+
+        if comment.user_id != user._id {
+            return Err(ApiError::Forbidden);
+        }
+
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let updated_comment = self
+            .collection
+            .find_one_and_update(
+                doc! { "_id": id, "deleted_at": Bson::Null },
+                audit::set_with_audit(doc! { "content": comment_data.content }, Some(user._id)),
+                options,
+            )
+            .await?;
+
+        updated_comment.ok_or(ApiError::NotFound)
+    }
+
+    // comment can be deleted by its author, author of the post or admin,
+    // replies are deleted together with it
+    pub async fn delete(
+        &self,
+        post_id: &PostId,
+        id: &CommentId,
+        user: &User,
+    ) -> Result<(), ApiError> {
+        let post = self.get_post(post_id, Some(user)).await?;
+
+        let Some(comment) = self.get_by_id(post_id, id).await? else {
+            return Err(ApiError::NotFound);
+        };
+
+        let can_delete = comment.user_id == user._id
+            || post.user_id == user._id
+            || user.role.satisfy(Role::Admin);
+
+        if !can_delete {
+            return Err(ApiError::Forbidden);
+        }
+
+        let result = self
+            .collection
+            .update_many(
+                doc! {
+                    "$or": [{ "_id": id }, { "ancestors": id }],
+                    "deleted_at": Bson::Null,
+                },
+                deletion::mark_deleted(user._id),
+                None,
+            )
+            .await?;
+
+        if result.modified_count == 0 {
+            return Ok(());
+        }
+
+        if let Some(parent_id) = comment.parent_id {
+            self.change_reply_count(&parent_id, -1).await?;
+        }
+
+        self.post_service
+            .change_comment_count(post_id, -(result.modified_count as i64))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_by_id(
+        &self,
+        post_id: &PostId,
+        id: &CommentId,
+    ) -> Result<Option<Comment>, ApiError> {
+        let comment = self
+            .collection
+            .find_one(
+                doc! {
+                    "_id": id,
+                    "post_id": post_id,
+                    "deleted_at": Bson::Null,
+                },
+                None,
+            )
+            .await?;
+
+        Ok(comment)
+    }
+
+    // comments of deleted or not yet visible post are not accessible
+    async fn get_post(&self, post_id: &PostId, viewer: Option<&User>) -> Result<Post, ApiError> {
+        self.post_service
+            .get_visible(post_id, viewer)
+            .await?
+            .ok_or(ApiError::NotFound)
+    }
+
+    async fn change_reply_count(&self, id: &CommentId, delta: i64) -> Result<(), ApiError> {
+        self.collection
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$inc": { "reply_count": delta } },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn new(db: Rc<Database>, post_service: Rc<PostService>) -> Self {
+        let collection = db.collection::<Comment>(Comment::COLLECTION_NAME);
+        CommentService {
+            db,
+            post_service,
+            collection,
+        }
+    }
+}
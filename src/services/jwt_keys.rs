@@ -0,0 +1,185 @@
+use std::{collections::HashMap, fmt, fs, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use rsa::{
+    pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey,
+};
+
+use crate::config;
+
+// DER prefix of SubjectPublicKeyInfo holding raw 32-byte Ed25519 key
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+enum KeyKind {
+    Symmetric,
+    Rsa,
+    Ed25519,
+}
+
+pub struct JwtKeys {
+    pub algorithm: Algorithm,
+    // signing key id, is not set for symmetric keys
+    pub key_id: Option<String>,
+    pub encoding_key: EncodingKey,
+    secret_decoding_key: Option<DecodingKey>,
+    decoding_keys: HashMap<String, DecodingKey>,
+    jwks: JwkSet,
+}
+
+impl JwtKeys {
+    pub fn from_config() -> Self {
+        let api_config = &config::get().api;
+
+        let algorithm = Algorithm::from_str(&api_config.jwt_algorithm)
+            .expect("JWT_ALGORITHM should be a valid JWT algorithm");
+
+        match key_kind(algorithm) {
+            KeyKind::Symmetric => JwtKeys {
+                algorithm,
+                key_id: None,
+                encoding_key: EncodingKey::from_secret(api_config.jwt_secret.as_bytes()),
+                secret_decoding_key: Some(DecodingKey::from_secret(
+                    api_config.jwt_secret.as_bytes(),
+                )),
+                decoding_keys: HashMap::new(),
+                jwks: JwkSet { keys: vec![] },
+            },
+            kind => Self::asymmetric(algorithm, kind),
+        }
+    }
+
+    fn asymmetric(algorithm: Algorithm, kind: KeyKind) -> Self {
+        let api_config = &config::get().api;
+
+        let private_key_path = api_config
+            .jwt_private_key_path
+            .as_ref()
+            .expect("JWT_PRIVATE_KEY_PATH is required for asymmetric JWT_ALGORITHM");
+        let private_key = read_key_file(private_key_path);
+
+        let encoding_key = match kind {
+            KeyKind::Rsa => EncodingKey::from_rsa_pem(&private_key),
+            _ => EncodingKey::from_ed_pem(&private_key),
+        }
+        .expect("JWT_PRIVATE_KEY_PATH should point to a valid PEM private key");
+
+        let mut jwks = JwkSet { keys: vec![] };
+        let mut decoding_keys = HashMap::new();
+
+        for (kid, path) in &api_config.jwt_public_keys {
+            let jwk = build_jwk(algorithm, &kind, kid, &read_key_file(path));
+            let decoding_key =
+                DecodingKey::from_jwk(&jwk).expect("Failed to build JWT decoding key from JWK");
+
+            decoding_keys.insert(kid.clone(), decoding_key);
+            jwks.keys.push(jwk);
+        }
+
+        if !decoding_keys.contains_key(&api_config.jwt_key_id) {
+            panic!(
+                "JWT_PUBLIC_KEYS should contain public key for JWT_KEY_ID \"{}\"",
+                api_config.jwt_key_id
+            );
+        }
+
+        JwtKeys {
+            algorithm,
+            key_id: Some(api_config.jwt_key_id.clone()),
+            encoding_key,
+            secret_decoding_key: None,
+            decoding_keys,
+            jwks,
+        }
+    }
+
+    pub fn decoding_key(&self, kid: Option<&str>) -> Option<&DecodingKey> {
+        match kid {
+            Some(kid) => self.decoding_keys.get(kid),
+            None => self.secret_decoding_key.as_ref(),
+        }
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+impl fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKeys")
+            .field("algorithm", &self.algorithm)
+            .field("key_id", &self.key_id)
+            .field("verification_key_ids", &self.decoding_keys.keys())
+            .finish()
+    }
+}
+
+fn key_kind(algorithm: Algorithm) -> KeyKind {
+    match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => KeyKind::Symmetric,
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => KeyKind::Rsa,
+        Algorithm::EdDSA => KeyKind::Ed25519,
+        _ => panic!("JWT_ALGORITHM {:?} is not supported", algorithm),
+    }
+}
+
+fn read_key_file(path: &str) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|e| panic!("Failed to read JWT key \"{}\": {}", path, e))
+}
+
+fn build_jwk(algorithm: Algorithm, kind: &KeyKind, kid: &str, public_key_pem: &[u8]) -> Jwk {
+    let public_key_pem = String::from_utf8_lossy(public_key_pem);
+
+    let algorithm_parameters = match kind {
+        KeyKind::Rsa => {
+            let public_key = RsaPublicKey::from_public_key_pem(&public_key_pem)
+                .or_else(|_e| RsaPublicKey::from_pkcs1_pem(&public_key_pem))
+                .unwrap_or_else(|e| panic!("JWT public key \"{}\" is not RSA: {}", kid, e));
+
+            AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+                e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+            })
+        }
+        _ => {
+            let der = pem::parse(public_key_pem.as_bytes())
+                .unwrap_or_else(|e| panic!("JWT public key \"{}\" is not PEM: {}", kid, e))
+                .into_contents();
+
+            let Some(raw_key) = der.strip_prefix(&ED25519_SPKI_PREFIX[..]) else {
+                panic!("JWT public key \"{}\" is not Ed25519", kid);
+            };
+
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(raw_key),
+            })
+        }
+    };
+
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: KeyAlgorithm::from_str(&format!("{:?}", algorithm)).ok(),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: algorithm_parameters,
+    }
+}
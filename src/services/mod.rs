@@ -1,7 +1,14 @@
 pub mod auth;
+pub mod comment;
+mod jwt_keys;
 pub mod post;
+pub mod reaction;
+pub mod token;
 pub mod user;
 
 pub use auth::AuthService;
+pub use comment::CommentService;
 pub use post::PostService;
+pub use reaction::ReactionService;
+pub use token::TokenService;
 pub use user::UserService;
@@ -1,93 +1,976 @@
-use std::rc::Rc;
+use std::{rc::Rc, time::Duration};
 
-use futures::{TryFutureExt, TryStreamExt};
+use futures::TryStreamExt;
 use mongodb::{
-    bson::{doc, oid::ObjectId},
-    Collection,
+    bson::oid::ObjectId,
+    bson::{doc, Bson, DateTime, Document},
+    error::TRANSIENT_TRANSACTION_ERROR,
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    ClientSession, Collection,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use validator::{Validate, ValidationError};
 
-use crate::models::{Database, DbError, Post};
+use crate::{
+    models::{
+        audit,
+        db::{self, Model},
+        deletion,
+        post::{self, PostStatus},
+        user::Role,
+        Audit, Database, DbError, Deletion, Post, PostId, PostRevision, Timestamp, User, UserId,
+    },
+    utils::{
+        diff::{self, DiffLine},
+        errors::ApiError,
+        highlight,
+        pagination::{self, Page, PageRequest, ScoredPageRequest, SortOrder},
+        trim,
+    },
+};
+
+const CONCURRENT_EDIT_MESSAGE: &str = "Post was changed by someone else, try again";
+// revision without post changed to it is left by failed update once it's that old
+const ABANDONED_REVISION_AGE: Duration = Duration::from_secs(60);
+
+pub const MAX_TITLE_LENGTH: u64 = 200;
+pub const MAX_CONTENT_LENGTH: u64 = 20000;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 30;
+
+// body limit generous enough for any post that can pass validation: escaped character takes
+// up to 12 bytes (`\ud83d\ude00`), padding leaves room for other fields and whitespace,
+// which json allows between tokens without limit, so very loosely formatted body may still hit it
+pub const MAX_PAYLOAD_SIZE: usize =
+    (MAX_TITLE_LENGTH as usize + MAX_CONTENT_LENGTH as usize + MAX_TAGS * MAX_TAG_LENGTH) * 12
+        + 64 * 1024;
 
 #[derive(Debug)]
 #[allow(unused)]
 pub struct PostService {
     db: Rc<Database>,
+    supports_transactions: bool,
 
     collection: Collection<Post>,
+    revision_collection: Collection<PostRevision>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreatePostData {
+    #[serde(deserialize_with = "trim::trimmed")]
+    #[validate(length(min = 1, max = MAX_TITLE_LENGTH, message = "Title should be 1-200 characters long"))]
     pub title: String,
+    #[serde(deserialize_with = "trim::trimmed")]
+    #[validate(length(
+        min = 1,
+        max = MAX_CONTENT_LENGTH,
+        message = "Content should be 1-20000 characters long"
+    ))]
     pub content: String,
+    #[serde(default, deserialize_with = "normalized_tags")]
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Vec<String>,
+    // published right away when not set
+    pub status: Option<PostStatus>,
+    // required for scheduled posts and accepted only for them
+    pub publish_at: Option<Timestamp>,
+}
+
+// only provided fields are changed
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePostData {
+    #[serde(default, deserialize_with = "trim::trimmed_option")]
+    #[validate(length(min = 1, max = MAX_TITLE_LENGTH, message = "Title should be 1-200 characters long"))]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "trim::trimmed_option")]
+    #[validate(length(
+        min = 1,
+        max = MAX_CONTENT_LENGTH,
+        message = "Content should be 1-20000 characters long"
+    ))]
+    pub content: Option<String>,
+    #[serde(default, deserialize_with = "normalized_tags_option")]
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Option<Vec<String>>,
+    pub status: Option<PostStatus>,
+    pub publish_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<SortOrder>,
+    pub author: Option<UserId>,
+    pub created_after: Option<Timestamp>,
+    pub status: Option<PostStatus>,
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostSearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub author: Option<UserId>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagListQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffQuery {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    pub from: i64,
+    pub to: i64,
+    pub title: Vec<DiffLine>,
+    pub content: Vec<DiffLine>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScoredPost {
+    #[serde(flatten)]
+    post: Post,
+    score: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Highlights {
+    pub title: Vec<String>,
+    pub content: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostSearchResult {
+    #[serde(flatten)]
+    pub post: Post,
+    pub score: f64,
+    pub highlights: Highlights,
 }
 
 impl PostService {
     pub async fn create(
         &self,
         post_data: CreatePostData,
-        user_id: &str,
-    ) -> Result<Post, mongodb::error::Error> {
-        let insert_result = self
+        user_id: &UserId,
+    ) -> Result<Post, ApiError> {
+        // there is no current status, so change is always resolved for new post
+        let Some((status, publish_at)) =
+            publication_change(None, post_data.status, post_data.publish_at)?
+        else {
+            return Err(ApiError::Internal);
+        };
+
+        let post_id = PostId::new();
+        let revision = new_revision(
+            &post_id,
+            1,
+            &post_data.title,
+            &post_data.content,
+            Some(*user_id),
+        );
+        let new_post = Post {
+            _id: post_id,
+            title: post_data.title,
+            content: post_data.content,
+            tags: post_data.tags,
+            user_id: *user_id,
+            status,
+            publish_at,
+            revision: 1,
+            comment_count: 0,
+            like_count: 0,
+            audit: Audit::new(Some(*user_id)),
+            deletion: Deletion::default(),
+        };
+
+        if self.supports_transactions {
+            self.insert_in_transaction(&new_post, &revision).await?;
+        } else {
+            self.insert_after_revision(&new_post, &revision).await?;
+        }
+
+        let post = self
+            .collection
+            .find_one(doc! { "_id": post_id }, None)
+            .await?;
+
+        let Some(post) = post else {
+            return Err(ApiError::Internal);
+        };
+
+        Ok(post)
+    }
+
+    // new post and its first revision are written together, same as on update
+    async fn insert_in_transaction(
+        &self,
+        post: &Post,
+        revision: &PostRevision,
+    ) -> Result<(), DbError> {
+        db::with_transaction(
+            self.collection.client(),
+            (self, post, revision),
+            |session, &(this, post, revision)| {
+                Box::pin(async move {
+                    this.revision_collection
+                        .insert_one_with_session(revision, None, session)
+                        .await?;
+                    this.collection
+                        .insert_one_with_session(post, None, session)
+                        .await?;
+
+                    Ok(())
+                })
+            },
+        )
+        .await
+    }
+
+    // standalone server has no transactions, so revision goes first and post never exists
+    // without history, revision is deleted back if post can't be stored
+    async fn insert_after_revision(
+        &self,
+        post: &Post,
+        revision: &PostRevision,
+    ) -> Result<(), DbError> {
+        self.revision_collection.insert_one(revision, None).await?;
+
+        if let Err(e) = self.collection.insert_one(post, None).await {
+            let delete_result = self
+                .revision_collection
+                .delete_one(doc! { "_id": revision._id }, None)
+                .await;
+
+            if let Err(delete_error) = delete_result {
+                println!(
+                    "db: failed to delete revision of post {} that failed to be created: {}",
+                    post._id, delete_error
+                );
+            }
+
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub async fn list(
+        &self,
+        query: PostListQuery,
+        viewer: Option<&User>,
+    ) -> Result<Page<Post>, ApiError> {
+        let conditions = vec![deletion::not_deleted(), post::visible_to(viewer)];
+        self.find_page(query, conditions).await
+    }
+
+    // admins see deleted posts regardless of their status
+    pub async fn list_deleted(&self, query: PostListQuery) -> Result<Page<Post>, ApiError> {
+        self.find_page(query, vec![deletion::deleted()]).await
+    }
+
+    async fn find_page(
+        &self,
+        query: PostListQuery,
+        mut conditions: Vec<Document>,
+    ) -> Result<Page<Post>, ApiError> {
+        let page = PageRequest::new(query.limit, query.cursor.as_deref(), query.sort)?;
+
+        if let Some(author) = query.author {
+            conditions.push(doc! { "user_id": author });
+        }
+
+        if let Some(created_after) = query.created_after {
+            conditions.push(doc! { "created_at": { "$gte": created_after } });
+        }
+
+        if let Some(status) = query.status {
+            conditions.push(doc! { "status": status });
+        }
+
+        if let Some(tag) = query.tag {
+            conditions.push(doc! { "tags": post::normalize_tag(&tag) });
+        }
+
+        conditions.extend(page.cursor_condition());
+
+        let posts: Vec<Post> = self
+            .collection
+            .find(pagination::and_filter(conditions), page.find_options())
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(page.into_page(posts, |post| post._id.into()))
+    }
+
+    // ordered by relevance, title matches weigh more than content ones
+    pub async fn search(
+        &self,
+        query: PostSearchQuery,
+        viewer: Option<&User>,
+    ) -> Result<Page<PostSearchResult>, ApiError> {
+        let text = query.q.trim();
+
+        if text.is_empty() || text.chars().count() > 200 {
+            return Err(ApiError::BadRequest(
+                "q should be 1-200 characters long".to_string(),
+            ));
+        }
+
+        let page = ScoredPageRequest::new(query.limit, query.cursor.as_deref())?;
+
+        // $text has to be in the first stage, so it is combined with other filters
+        let mut text_match = doc! {
+            "$text": { "$search": text },
+            "deleted_at": Bson::Null,
+            "$and": [post::visible_to(viewer)],
+        };
+
+        if let Some(author) = query.author {
+            text_match.insert("user_id", author);
+        }
+
+        let mut pipeline = vec![
+            doc! { "$match": text_match },
+            doc! { "$addFields": { "score": { "$meta": "textScore" } } },
+        ];
+
+        if let Some(cursor_condition) = page.cursor_condition() {
+            pipeline.push(doc! { "$match": cursor_condition });
+        }
+
+        pipeline.push(doc! { "$sort": page.sort() });
+        pipeline.push(doc! { "$limit": page.fetch_limit() });
+
+        let scored_posts: Vec<ScoredPost> = self
+            .collection
+            .aggregate(pipeline, None)
+            .await?
+            .with_type::<ScoredPost>()
+            .try_collect()
+            .await?;
+
+        let scored_page = page.into_page(scored_posts, |p| (p.score, p.post._id.into()));
+        let terms = highlight::query_terms(text);
+
+        Ok(Page {
+            items: scored_page
+                .items
+                .into_iter()
+                .map(|ScoredPost { post, score }| PostSearchResult {
+                    highlights: Highlights {
+                        title: highlight::snippets(&post.title, &terms),
+                        content: highlight::snippets(&post.content, &terms),
+                    },
+                    post,
+                    score,
+                })
+                .collect(),
+            next_cursor: scored_page.next_cursor,
+        })
+    }
+
+    // most used tags first, only published posts are counted
+    pub async fn tag_counts(&self, query: TagListQuery) -> Result<Vec<TagCount>, ApiError> {
+        let limit = pagination::validate_limit(query.limit)?;
+
+        let pipeline = vec![
+            doc! { "$match": { "deleted_at": Bson::Null, "$and": [post::published()] } },
+            doc! { "$unwind": "$tags" },
+            doc! { "$group": { "_id": "$tags", "count": { "$sum": 1 } } },
+            doc! { "$sort": { "count": -1, "_id": 1 } },
+            doc! { "$limit": limit },
+            doc! { "$project": { "_id": 0, "tag": "$_id", "count": 1 } },
+        ];
+
+        let tag_counts = self
+            .collection
+            .aggregate(pipeline, None)
+            .await?
+            .with_type::<TagCount>()
+            .try_collect()
+            .await?;
+
+        Ok(tag_counts)
+    }
+
+    pub async fn get_by_id(&self, id: &PostId) -> Result<Option<Post>, DbError> {
+        let filter = doc! { "_id": id, "deleted_at": Bson::Null };
+        self.collection.find_one(filter, None).await
+    }
+
+    // post is reported missing to those who can't see it yet
+    pub async fn get_visible(
+        &self,
+        id: &PostId,
+        viewer: Option<&User>,
+    ) -> Result<Option<Post>, DbError> {
+        let filter = doc! {
+            "_id": id,
+            "deleted_at": Bson::Null,
+            "$and": [post::visible_to(viewer)],
+        };
+        self.collection.find_one(filter, None).await
+    }
+
+    pub async fn update(
+        &self,
+        id: &PostId,
+        post_data: UpdatePostData,
+        user: &User,
+    ) -> Result<Post, ApiError> {
+        let post = self.get_modifiable(id, user).await?;
+
+        self.apply_update(post, post_data, user).await
+    }
+
+    async fn apply_update(
+        &self,
+        post: Post,
+        post_data: UpdatePostData,
+        user: &User,
+    ) -> Result<Post, ApiError> {
+        let mut changes = doc! {};
+
+        // status changes don't produce revisions, only title and content are kept in history
+        let is_revised = post_data.title.as_ref().is_some_and(|t| *t != post.title)
+            || post_data
+                .content
+                .as_ref()
+                .is_some_and(|c| *c != post.content);
+
+        let revised_title = post_data
+            .title
+            .clone()
+            .unwrap_or_else(|| post.title.clone());
+        let revised_content = post_data
+            .content
+            .clone()
+            .unwrap_or_else(|| post.content.clone());
+
+        if let Some(title) = post_data.title {
+            changes.insert("title", title);
+        }
+
+        if let Some(content) = post_data.content {
+            changes.insert("content", content);
+        }
+
+        if let Some(tags) = post_data.tags {
+            changes.insert("tags", tags);
+        }
+
+        if let Some((status, publish_at)) =
+            publication_change(Some(&post), post_data.status, post_data.publish_at)?
+        {
+            changes.insert("status", status);
+            changes.insert("publish_at", publish_at);
+        }
+
+        if changes.is_empty() {
+            return Ok(post);
+        }
+
+        let mut update = audit::set_with_audit(changes, Some(user._id));
+
+        if !is_revised {
+            let updated_post = self
+                .collection
+                .find_one_and_update(
+                    doc! { "_id": post._id, "deleted_at": Bson::Null },
+                    update,
+                    updated_document_options(),
+                )
+                .await?;
+
+            return updated_post.ok_or(ApiError::NotFound);
+        }
+
+        // number is taken atomically, so concurrent edits never share revision
+        update.insert("$inc", doc! { "revision": 1 });
+
+        if self.supports_transactions {
+            self.update_in_transaction(&post, update, user).await
+        } else {
+            let revision = new_revision(
+                &post._id,
+                post.revision + 1,
+                &revised_title,
+                &revised_content,
+                Some(user._id),
+            );
+            self.update_after_revision(&post, update, revision).await
+        }
+    }
+
+    // post and its revision are written together, so history never misses a change
+    async fn update_in_transaction(
+        &self,
+        post: &Post,
+        update: Document,
+        user: &User,
+    ) -> Result<Post, ApiError> {
+        let updated_post = db::with_transaction(
+            self.collection.client(),
+            (self, post, &update, user),
+            |session, &(this, post, update, user)| {
+                Box::pin(async move {
+                    let updated_post = this
+                        .collection
+                        .find_one_and_update_with_session(
+                            doc! { "_id": post._id, "deleted_at": Bson::Null },
+                            update.clone(),
+                            updated_document_options(),
+                            session,
+                        )
+                        .await?;
+
+                    let Some(updated_post) = updated_post else {
+                        return Ok(None);
+                    };
+
+                    let revision = new_revision(
+                        &updated_post._id,
+                        updated_post.revision,
+                        &updated_post.title,
+                        &updated_post.content,
+                        Some(user._id),
+                    );
+                    this.revision_collection
+                        .insert_one_with_session(&revision, None, session)
+                        .await?;
+
+                    Ok(Some(updated_post))
+                })
+            },
+        )
+        .await
+        .map_err(concurrent_edit_err)?;
+
+        updated_post.ok_or(ApiError::NotFound)
+    }
+
+    // standalone server has no transactions, so revision is stored first under number post is
+    // expected to get, and post is changed only while it still has previous one;
+    // revisions ahead of their post are never listed, so failed update leaves no trace in history
+    async fn update_after_revision(
+        &self,
+        post: &Post,
+        update: Document,
+        revision: PostRevision,
+    ) -> Result<Post, ApiError> {
+        if let Err(e) = self.revision_collection.insert_one(&revision, None).await {
+            if !db::is_duplicate_key_error(&e) {
+                return Err(e.into());
+            }
+
+            // number is either taken by concurrent edit, or left by update that failed midway
+            let abandoned_before = DateTime::from_millis(
+                DateTime::now().timestamp_millis() - ABANDONED_REVISION_AGE.as_millis() as i64,
+            );
+            let delete_result = self
+                .revision_collection
+                .delete_one(
+                    doc! {
+                        "post_id": post._id,
+                        "rev": revision.rev,
+                        "created_at": { "$lt": abandoned_before },
+                    },
+                    None,
+                )
+                .await?;
+
+            if delete_result.deleted_count == 0 {
+                return Err(concurrent_edit_err(e));
+            }
+
+            self.revision_collection
+                .insert_one(&revision, None)
+                .await
+                .map_err(concurrent_edit_err)?;
+        }
+
+        let updated_post = self
             .collection
-            .insert_one(
-                Post {
-                    _id: ObjectId::new().to_hex(),
-                    title: post_data.title,
-                    content: post_data.content,
-                    user_id: user_id.to_string(),
+            .find_one_and_update(
+                doc! {
+                    "_id": post._id,
+                    "revision": post.revision,
+                    "deleted_at": Bson::Null,
                 },
-                None,
+                update,
+                updated_document_options(),
             )
-            .await;
+            .await?;
 
-        if insert_result.is_err() {
-            return Err(insert_result.unwrap_err());
+        if let Some(updated_post) = updated_post {
+            return Ok(updated_post);
         }
 
-        let insert_result = insert_result.unwrap();
+        self.revision_collection
+            .delete_one(doc! { "_id": revision._id }, None)
+            .await?;
 
+        match self.get_by_id(&post._id).await? {
+            Some(_) => Err(ApiError::Conflict(CONCURRENT_EDIT_MESSAGE.to_string())),
+            None => Err(ApiError::NotFound),
+        }
+    }
+
+    // history is readable by anyone who can see the post, latest revision first
+    pub async fn list_revisions(
+        &self,
+        id: &PostId,
+        query: RevisionListQuery,
+        viewer: Option<&User>,
+    ) -> Result<Page<PostRevision>, ApiError> {
         let post = self
+            .get_visible(id, viewer)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+        let page = PageRequest::new(query.limit, query.cursor.as_deref(), None)?;
+
+        let mut conditions = vec![doc! { "post_id": id, "rev": { "$lte": post.revision } }];
+        conditions.extend(page.cursor_condition());
+
+        let revisions: Vec<PostRevision> = self
+            .revision_collection
+            .find(pagination::and_filter(conditions), page.find_options())
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(page.into_page(revisions, |revision| revision._id))
+    }
+
+    pub async fn get_revision(
+        &self,
+        id: &PostId,
+        rev: i64,
+        viewer: Option<&User>,
+    ) -> Result<PostRevision, ApiError> {
+        let post = self
+            .get_visible(id, viewer)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+        self.find_revision(&post, rev).await
+    }
+
+    pub async fn diff_revisions(
+        &self,
+        id: &PostId,
+        query: RevisionDiffQuery,
+        viewer: Option<&User>,
+    ) -> Result<RevisionDiff, ApiError> {
+        let post = self
+            .get_visible(id, viewer)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+        if query.from.max(query.to) > post.revision {
+            return Err(ApiError::NotFound);
+        }
+
+        let options = FindOptions::builder().sort(doc! { "rev": 1 }).build();
+
+        let revisions: Vec<PostRevision> = self
+            .revision_collection
+            .find(
+                doc! { "post_id": id, "rev": { "$in": [query.from, query.to] } },
+                options,
+            )
+            .await?
+            .try_collect()
+            .await?;
+
+        let find = |rev| revisions.iter().find(|revision| revision.rev == rev);
+
+        let (Some(from), Some(to)) = (find(query.from), find(query.to)) else {
+            return Err(ApiError::NotFound);
+        };
+
+        Ok(RevisionDiff {
+            from: from.rev,
+            to: to.rev,
+            title: diff::lines(&from.title, &to.title),
+            content: diff::lines(&from.content, &to.content),
+        })
+    }
+
+    // restored content is saved as new revision, so history itself never changes
+    pub async fn restore_revision(
+        &self,
+        id: &PostId,
+        rev: i64,
+        user: &User,
+    ) -> Result<Post, ApiError> {
+        let post = self.get_modifiable(id, user).await?;
+
+        let revision = self.find_revision(&post, rev).await?;
+
+        self.apply_update(
+            post,
+            UpdatePostData {
+                title: Some(revision.title),
+                content: Some(revision.content),
+                tags: None,
+                status: None,
+                publish_at: None,
+            },
+            user,
+        )
+        .await
+    }
+
+    // revisions ahead of post belong to edits that haven't completed
+    async fn find_revision(&self, post: &Post, rev: i64) -> Result<PostRevision, ApiError> {
+        if rev > post.revision {
+            return Err(ApiError::NotFound);
+        }
+
+        self.revision_collection
+            .find_one(doc! { "post_id": post._id, "rev": rev }, None)
+            .await?
+            .ok_or(ApiError::NotFound)
+    }
+
+    pub async fn delete(&self, id: &PostId, user: &User) -> Result<(), ApiError> {
+        self.get_modifiable(id, user).await?;
+
+        self.collection
+            .update_one(
+                doc! { "_id": id, "deleted_at": Bson::Null },
+                deletion::mark_deleted(user._id),
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn restore(&self, id: &PostId, admin: &User) -> Result<Post, ApiError> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let restored_post = self
             .collection
-            .find_one(
-                doc! {
-                    "_id": insert_result.inserted_id
-                },
+            .find_one_and_update(
+                doc! { "_id": id, "deleted_at": { "$ne": Bson::Null } },
+                deletion::mark_restored(admin._id),
+                options,
+            )
+            .await?;
+
+        restored_post.ok_or(ApiError::NotFound)
+    }
+
+    pub async fn change_comment_count(&self, id: &PostId, delta: i64) -> Result<(), DbError> {
+        self.change_counter(id, "comment_count", delta).await
+    }
+
+    pub async fn change_like_count(&self, id: &PostId, delta: i64) -> Result<(), DbError> {
+        self.change_counter(id, "like_count", delta).await
+    }
+
+    pub async fn change_like_count_with_session(
+        &self,
+        id: &PostId,
+        delta: i64,
+        session: &mut ClientSession,
+    ) -> Result<(), DbError> {
+        self.collection
+            .update_one_with_session(
+                doc! { "_id": id },
+                doc! { "$inc": { "like_count": delta } },
                 None,
+                session,
             )
-            .await;
+            .await?;
 
-        if post.is_err() {
-            return Err(post.unwrap_err());
-        }
+        Ok(())
+    }
 
-        return Ok(post.unwrap().unwrap());
+    // counters are not changes made to post, so audit fields are kept as is
+    async fn change_counter(&self, id: &PostId, counter: &str, delta: i64) -> Result<(), DbError> {
+        self.collection
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$inc": { counter: delta } },
+                None,
+            )
+            .await?;
+
+        Ok(())
     }
 
-    pub async fn list(&self) -> Result<Vec<Post>, DbError> {
-        let result = self.collection.find(None, None).await;
+    // post can be changed only by its author or admin
+    async fn get_modifiable(&self, id: &PostId, user: &User) -> Result<Post, ApiError> {
+        let Some(post) = self.get_by_id(id).await? else {
+            return Err(ApiError::NotFound);
+        };
 
-        if result.is_err() {
-            return Err(result.unwrap_err());
+        if post.user_id != user._id && !user.role.satisfy(Role::Admin) {
+            return Err(ApiError::Forbidden);
         }
 
-        let result = result.unwrap();
+        Ok(post)
+    }
+
+    pub fn new(db: Rc<Database>, supports_transactions: bool) -> Self {
+        let collection = db.collection::<Post>(Post::COLLECTION_NAME);
+        let revision_collection = db.collection::<PostRevision>(PostRevision::COLLECTION_NAME);
+        PostService {
+            db,
+            supports_transactions,
+            collection,
+            revision_collection,
+        }
+    }
+}
+
+fn new_revision(
+    post_id: &PostId,
+    rev: i64,
+    title: &str,
+    content: &str,
+    actor: Option<UserId>,
+) -> PostRevision {
+    PostRevision {
+        _id: ObjectId::new(),
+        post_id: *post_id,
+        rev,
+        title: title.to_string(),
+        content: content.to_string(),
+        created_at: Timestamp::now(),
+        created_by: actor,
+    }
+}
+
+fn updated_document_options() -> FindOneAndUpdateOptions {
+    FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build()
+}
+
+// concurrent edits of the same post conflict on revision number or in transaction
+fn concurrent_edit_err(e: DbError) -> ApiError {
+    if db::is_duplicate_key_error(&e) || e.contains_label(TRANSIENT_TRANSACTION_ERROR) {
+        return ApiError::Conflict(CONCURRENT_EDIT_MESSAGE.to_string());
+    }
+
+    e.into()
+}
+
+fn normalized_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tags = Vec::<String>::deserialize(deserializer)?;
+    Ok(post::normalize_tags(tags))
+}
+
+fn normalized_tags_option<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tags = Option::<Vec<String>>::deserialize(deserializer)?;
+    Ok(tags.map(post::normalize_tags))
+}
+
+// checked after normalization, so limits apply to stored form
+fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > MAX_TAGS {
+        return Err(ValidationError::new("too_many_tags")
+            .with_message("Post can have at most 10 tags".into()));
+    }
 
-        let posts: Vec<Post> = result.try_collect().unwrap_or_else(|_e| vec![]).await;
+    let is_valid = |tag: &String| {
+        tag.chars().count() <= MAX_TAG_LENGTH
+            && tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    };
 
-        Ok(posts)
+    if !tags.iter().all(is_valid) {
+        return Err(ValidationError::new("invalid_tag").with_message(
+            "Tags should be up to 30 letters, digits, dashes or underscores".into(),
+        ));
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Option<Post> {
-        let filter = doc! { "_id": id };
-        let result = self.collection.find_one(filter, None).await;
+    Ok(())
+}
+
+// resolves status and publish_at the post should get, None when they stay as they are;
+// post is created published unless other status is requested
+fn publication_change(
+    current: Option<&Post>,
+    status: Option<PostStatus>,
+    publish_at: Option<Timestamp>,
+) -> Result<Option<(PostStatus, Option<Timestamp>)>, ApiError> {
+    let current_status = current.map(|post| post.status);
+    let status = status.or(current_status).unwrap_or_default();
 
-        return result.unwrap_or_else(|_e| None);
+    if publish_at.is_some() && status != PostStatus::Scheduled {
+        return Err(ApiError::BadRequest(
+            "publish_at can be set only for scheduled posts".to_string(),
+        ));
     }
 
-    pub fn new(db: Rc<Database>) -> Self {
-        let collection = db.collection::<Post>("posts");
-        PostService { db, collection }
+    if current_status == Some(status) && publish_at.is_none() {
+        return Ok(None);
     }
+
+    let change = match status {
+        PostStatus::Draft => (status, None),
+        PostStatus::Published => (status, Some(Timestamp::now())),
+        PostStatus::Scheduled => {
+            let Some(publish_at) = publish_at else {
+                return Err(ApiError::BadRequest(
+                    "publish_at is required for scheduled posts".to_string(),
+                ));
+            };
+
+            if publish_at <= Timestamp::now() {
+                return Err(ApiError::BadRequest(
+                    "publish_at should be in the future".to_string(),
+                ));
+            }
+
+            (status, Some(publish_at))
+        }
+        // archived post keeps time it was published at
+        PostStatus::Archived => match current {
+            Some(post) => (status, post.publish_at),
+            None => {
+                return Err(ApiError::BadRequest(
+                    "Post can't be created archived".to_string(),
+                ))
+            }
+        },
+    };
+
+    Ok(Some(change))
 }
@@ -0,0 +1,232 @@
+use std::{collections::HashSet, rc::Rc};
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    ClientSession, Collection,
+};
+use serde::Serialize;
+
+use crate::{
+    models::{
+        db::{self, Model},
+        Database, DbError, Post, PostId, Reaction, Timestamp, User,
+    },
+    services::PostService,
+    utils::{errors::ApiError, pagination::Page},
+};
+
+fn like_delta(liked: bool) -> i64 {
+    if liked {
+        1
+    } else {
+        -1
+    }
+}
+
+#[derive(Debug)]
+#[allow(unused)]
+pub struct ReactionService {
+    db: Rc<Database>,
+    post_service: Rc<PostService>,
+    supports_transactions: bool,
+    collection: Collection<Reaction>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LikeState {
+    pub like_count: i64,
+    pub liked_by_me: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostListItem {
+    #[serde(flatten)]
+    pub post: Post,
+    // only present for authenticated caller
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liked_by_me: Option<bool>,
+}
+
+impl ReactionService {
+    // repeated like is no-op, counter is changed only when reaction is actually stored
+    pub async fn like(&self, post_id: &PostId, user: &User) -> Result<LikeState, ApiError> {
+        self.get_post(post_id, user).await?;
+
+        self.change_reaction(post_id, user, true).await?;
+
+        self.like_state(post_id, user, true).await
+    }
+
+    pub async fn unlike(&self, post_id: &PostId, user: &User) -> Result<LikeState, ApiError> {
+        self.get_post(post_id, user).await?;
+
+        self.change_reaction(post_id, user, false).await?;
+
+        self.like_state(post_id, user, false).await
+    }
+
+    // reaction and like_count are changed in one transaction, standalone server has none,
+    // so there counter is changed right after reaction, `repair-like-counts` fixes drift
+    async fn change_reaction(
+        &self,
+        post_id: &PostId,
+        user: &User,
+        liked: bool,
+    ) -> Result<(), ApiError> {
+        if !self.supports_transactions {
+            let changed = match self.write_reaction(post_id, user, liked, None).await {
+                Err(e) if db::is_duplicate_key_error(&e) => false,
+                changed => changed?,
+            };
+
+            if changed {
+                self.post_service
+                    .change_like_count(post_id, like_delta(liked))
+                    .await?;
+            }
+
+            return Ok(());
+        }
+
+        let result = db::with_transaction(
+            self.collection.client(),
+            (self, post_id, user),
+            |session, &(this, post_id, user)| {
+                Box::pin(async move {
+                    if this
+                        .write_reaction(post_id, user, liked, Some(&mut *session))
+                        .await?
+                    {
+                        this.post_service
+                            .change_like_count_with_session(post_id, like_delta(liked), session)
+                            .await?;
+                    }
+
+                    Ok::<_, DbError>(())
+                })
+            },
+        )
+        .await;
+
+        // duplicate like aborts transaction on server as well, so nothing is committed then
+        match result {
+            Err(e) if db::is_duplicate_key_error(&e) => Ok(()),
+            result => Ok(result?),
+        }
+    }
+
+    // stores or removes reaction of user, returns whether anything has changed;
+    // repeated like fails with duplicate key error instead
+    async fn write_reaction(
+        &self,
+        post_id: &PostId,
+        user: &User,
+        liked: bool,
+        session: Option<&mut ClientSession>,
+    ) -> Result<bool, DbError> {
+        if !liked {
+            let filter = doc! { "post_id": post_id, "user_id": user._id };
+            let delete_result = match session {
+                Some(session) => {
+                    self.collection
+                        .delete_one_with_session(filter, None, session)
+                        .await?
+                }
+                None => self.collection.delete_one(filter, None).await?,
+            };
+
+            return Ok(delete_result.deleted_count > 0);
+        }
+
+        let reaction = Reaction {
+            _id: ObjectId::new(),
+            post_id: *post_id,
+            user_id: user._id,
+            created_at: Timestamp::now(),
+        };
+
+        match session {
+            Some(session) => {
+                self.collection
+                    .insert_one_with_session(&reaction, None, session)
+                    .await?
+            }
+            None => self.collection.insert_one(&reaction, None).await?,
+        };
+
+        Ok(true)
+    }
+
+    // adds liked_by_me to listed posts, with single query for the whole page
+    pub async fn mark_liked(
+        &self,
+        page: Page<Post>,
+        user: Option<&User>,
+    ) -> Result<Page<PostListItem>, ApiError> {
+        let liked = match user {
+            Some(user) => Some(self.liked_post_ids(&page.items, user).await?),
+            None => None,
+        };
+
+        Ok(page.map(|post| PostListItem {
+            liked_by_me: liked.as_ref().map(|liked| liked.contains(&post._id)),
+            post,
+        }))
+    }
+
+    async fn liked_post_ids(
+        &self,
+        posts: &[Post],
+        user: &User,
+    ) -> Result<HashSet<PostId>, ApiError> {
+        let post_ids: Vec<PostId> = posts.iter().map(|post| post._id).collect();
+
+        let reactions: Vec<Reaction> = self
+            .collection
+            .find(
+                doc! { "user_id": user._id, "post_id": { "$in": post_ids } },
+                None,
+            )
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(reactions.into_iter().map(|r| r.post_id).collect())
+    }
+
+    async fn like_state(
+        &self,
+        post_id: &PostId,
+        user: &User,
+        liked_by_me: bool,
+    ) -> Result<LikeState, ApiError> {
+        let post = self.get_post(post_id, user).await?;
+
+        Ok(LikeState {
+            like_count: post.like_count,
+            liked_by_me,
+        })
+    }
+
+    async fn get_post(&self, post_id: &PostId, user: &User) -> Result<Post, ApiError> {
+        self.post_service
+            .get_visible(post_id, Some(user))
+            .await?
+            .ok_or(ApiError::NotFound)
+    }
+
+    pub fn new(
+        db: Rc<Database>,
+        post_service: Rc<PostService>,
+        supports_transactions: bool,
+    ) -> Self {
+        let collection = db.collection::<Reaction>(Reaction::COLLECTION_NAME);
+        ReactionService {
+            db,
+            post_service,
+            supports_transactions,
+            collection,
+        }
+    }
+}
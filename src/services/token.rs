@@ -0,0 +1,266 @@
+use std::{rc::Rc, time::Duration};
+
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Collection,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config,
+    models::{db::Model, Database, DbError, RefreshToken, RevokedToken, User, UserId},
+    services::{auth::Claims, AuthService},
+    utils::errors::ApiError,
+};
+
+#[derive(Debug)]
+#[allow(unused)]
+pub struct TokenService {
+    db: Rc<Database>,
+    auth_service: Rc<AuthService>,
+    collection: Collection<RefreshToken>,
+    revoked_collection: Collection<RevokedToken>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenData {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LogoutData {
+    pub refresh_token: Option<String>,
+    // logout from every device
+    #[serde(default)]
+    pub all: bool,
+}
+
+impl TokenService {
+    pub async fn issue_tokens(&self, user: &User) -> Result<TokenPair, ApiError> {
+        let family_id = ObjectId::new().to_hex();
+        self.issue_tokens_in_family(user, &family_id).await
+    }
+
+    pub async fn issue_rotated_tokens(
+        &self,
+        user: &User,
+        used_token: &RefreshToken,
+    ) -> Result<TokenPair, ApiError> {
+        self.issue_tokens_in_family(user, &used_token.family_id)
+            .await
+    }
+
+    // marks presented refresh token as used and returns it, so it can never be used again;
+    // replaying already used token revokes the whole family as it is probably stolen
+    pub async fn use_refresh_token(&self, refresh_token: &str) -> Result<RefreshToken, ApiError> {
+        let token_hash = hash_token(refresh_token);
+
+        let rotate_result = self
+            .collection
+            .find_one_and_update(
+                doc! {
+                    "token_hash": &token_hash,
+                    "revoked": false,
+                },
+                doc! {
+                    "$set": { "revoked": true }
+                },
+                None,
+            )
+            .await?;
+
+        if let Some(stored_token) = rotate_result {
+            if stored_token.expires_at < DateTime::now() {
+                return Err(ApiError::Unauthorized);
+            }
+
+            return Ok(stored_token);
+        }
+
+        let reused_token = self
+            .collection
+            .find_one(doc! { "token_hash": &token_hash }, None)
+            .await;
+
+        if let Ok(Some(reused_token)) = reused_token {
+            println!(
+                "auth: refresh token reuse detected, revoking family {}",
+                reused_token.family_id
+            );
+            self.revoke_family(&reused_token.family_id).await?;
+        }
+
+        Err(ApiError::Unauthorized)
+    }
+
+    pub async fn revoke_family(&self, family_id: &str) -> Result<(), ApiError> {
+        self.collection
+            .update_many(
+                doc! { "family_id": family_id },
+                doc! {
+                    "$set": { "revoked": true }
+                },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn logout(&self, claims: &Claims, logout_data: LogoutData) -> Result<(), ApiError> {
+        if logout_data.all {
+            return self.revoke_all(&claims.user_id).await;
+        }
+
+        self.revoke_access_token(claims).await?;
+
+        let Some(refresh_token) = logout_data.refresh_token else {
+            return Ok(());
+        };
+
+        let stored_token = self
+            .collection
+            .find_one(
+                doc! {
+                    "token_hash": hash_token(&refresh_token),
+                    "user_id": &claims.user_id,
+                },
+                None,
+            )
+            .await?;
+
+        match stored_token {
+            Some(stored_token) => self.revoke_family(&stored_token.family_id).await,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn is_revoked(&self, claims: &Claims) -> Result<bool, DbError> {
+        let revoked_token = self
+            .revoked_collection
+            .find_one(
+                doc! {
+                    "$or": [
+                        { "jti": &claims.jti },
+                        {
+                            "user_id": &claims.user_id,
+                            "issued_before": { "$gt": claims.iat_ms as i64 },
+                        },
+                    ]
+                },
+                None,
+            )
+            .await?;
+
+        Ok(revoked_token.is_some())
+    }
+
+    async fn revoke_access_token(&self, claims: &Claims) -> Result<(), ApiError> {
+        self.revoked_collection
+            .insert_one(
+                RevokedToken {
+                    _id: ObjectId::new(),
+                    user_id: claims.user_id,
+                    jti: Some(claims.jti.clone()),
+                    issued_before: None,
+                    expires_at: DateTime::from_millis(claims.exp as i64 * 1000),
+                },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_all(&self, user_id: &UserId) -> Result<(), ApiError> {
+        let now = DateTime::now();
+        let access_token_ttl = Duration::from_secs(config::get().api.access_token_ttl);
+
+        self.revoked_collection
+            .insert_one(
+                RevokedToken {
+                    _id: ObjectId::new(),
+                    user_id: *user_id,
+                    jti: None,
+                    issued_before: Some(now.timestamp_millis()),
+                    // every token issued before now is expired after this moment anyway
+                    expires_at: now.saturating_add_duration(access_token_ttl),
+                },
+                None,
+            )
+            .await?;
+
+        self.collection
+            .update_many(
+                doc! { "user_id": user_id },
+                doc! {
+                    "$set": { "revoked": true }
+                },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn issue_tokens_in_family(
+        &self,
+        user: &User,
+        family_id: &str,
+    ) -> Result<TokenPair, ApiError> {
+        let token = self.auth_service.create_token(user)?;
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let refresh_token = bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+
+        let ttl = Duration::from_secs(config::get().api.refresh_token_ttl);
+
+        self.collection
+            .insert_one(
+                RefreshToken {
+                    _id: ObjectId::new(),
+                    user_id: user._id,
+                    family_id: family_id.to_string(),
+                    token_hash: hash_token(&refresh_token),
+                    expires_at: DateTime::now().saturating_add_duration(ttl),
+                    revoked: false,
+                },
+                None,
+            )
+            .await?;
+
+        Ok(TokenPair {
+            token,
+            refresh_token,
+        })
+    }
+
+    pub fn new(db: Rc<Database>, auth_service: Rc<AuthService>) -> Self {
+        let collection: Collection<RefreshToken> = db.collection(RefreshToken::COLLECTION_NAME);
+        let revoked_collection: Collection<RevokedToken> =
+            db.collection(RevokedToken::COLLECTION_NAME);
+        TokenService {
+            db,
+            auth_service,
+            collection,
+            revoked_collection,
+        }
+    }
+}
+
+// refresh tokens are random enough, so plain sha256 is sufficient to not keep them readable in db
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
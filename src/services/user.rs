@@ -1,209 +1,440 @@
 use std::rc::Rc;
 
 use actix_web::HttpRequest;
-use futures::{TryFutureExt, TryStreamExt};
+use futures::TryStreamExt;
 use mongodb::{
-    bson::{doc, oid::ObjectId},
+    bson::{doc, oid::ObjectId, Bson, Document},
+    options::{FindOneAndUpdateOptions, FindOneOptions, ReturnDocument},
     Collection,
 };
 
 use serde::Deserialize;
+use validator::{Validate, ValidationError};
 
 use crate::{
     models::{
-        user::{Role, UserAuth},
-        Database, DbError, User,
+        audit,
+        db::{self, Model},
+        deletion,
+        user::{self, Role, UserAuth},
+        Audit, Database, DbError, Deletion, Timestamp, User, UserId,
+    },
+    services::{
+        auth::{Claims, PasswordCheck},
+        AuthService, TokenService,
+    },
+    utils::{
+        errors::ApiError,
+        fail_point,
+        pagination::{self, Page, PageRequest, SortOrder},
+        trim,
     },
-    services::AuthService,
-    utils::errors,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct CreateUserData {
+    #[serde(deserialize_with = "trim::trimmed")]
+    #[validate(
+        email(message = "Email is invalid"),
+        length(max = 254, message = "Email is too long")
+    )]
     pub email: String,
+    #[validate(custom(function = "validate_password"))]
     pub password: String,
 }
 
+// password policy is not checked on login, so users with older passwords still can sign in
+#[derive(Deserialize)]
+pub struct LoginUserData {
+    #[serde(deserialize_with = "trim::trimmed")]
+    pub email: String,
+    pub password: String,
+}
+
+fn validate_password(password: &str) -> Result<(), ValidationError> {
+    let length = password.chars().count();
+
+    if length < 8 {
+        return Err(ValidationError::new("password_too_short")
+            .with_message("Password should be at least 8 characters long".into()));
+    }
+
+    if length > 128 {
+        return Err(ValidationError::new("password_too_long")
+            .with_message("Password should be at most 128 characters long".into()));
+    }
+
+    let has_letter = password.chars().any(|c| c.is_alphabetic());
+    let has_digit = password.chars().any(|c| c.is_ascii_digit());
+
+    if !has_letter || !has_digit {
+        return Err(ValidationError::new("password_too_weak")
+            .with_message("Password should contain both letters and digits".into()));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<SortOrder>,
+    pub role: Option<Role>,
+    pub created_after: Option<Timestamp>,
+}
+
 #[derive(Debug)]
 #[allow(unused)]
 pub struct UserService {
     db: Rc<Database>,
     auth_service: Rc<AuthService>,
+    token_service: Rc<TokenService>,
+    supports_transactions: bool,
     user_collection: Collection<User>,
     user_auth_collection: Collection<UserAuth>,
 }
 
 impl UserService {
-    pub async fn get_user_from_req(&self, req: &HttpRequest) -> Result<User, errors::Error> {
+    pub async fn get_claims_from_req(&self, req: &HttpRequest) -> Result<Claims, ApiError> {
         let Some(auth_header) = req.headers().get("Authorization") else {
-            return Err(errors::build_unauth_err());
+            return Err(ApiError::Unauthorized);
         };
 
         let Ok(auth_header) = auth_header.to_str() else {
-            return Err(errors::build_unauth_err());
+            return Err(ApiError::Unauthorized);
         };
 
-        if !auth_header.starts_with("Bearer ") {
-            return Err(errors::build_unauth_err());
-        }
+        let Some(token) = auth_header.strip_prefix("Bearer ") else {
+            return Err(ApiError::Unauthorized);
+        };
 
-        let token = auth_header.replace("Bearer ", "");
-        let decoded_token = self.auth_service.decode_token(token.as_str());
+        let claims = self.auth_service.decode_token(token)?;
 
-        if decoded_token.is_err() {
-            return Err(errors::build_unauth_err());
+        if self.token_service.is_revoked(&claims).await? {
+            return Err(ApiError::Unauthorized);
         }
 
-        let decoded_token = decoded_token.unwrap();
+        Ok(claims)
+    }
+
+    pub async fn get_user_from_req(&self, req: &HttpRequest) -> Result<User, ApiError> {
+        let claims = self.get_claims_from_req(req).await?;
 
-        let user = self.get_by_id(&decoded_token.user_id).await;
+        let user = self.get_by_id(&claims.user_id).await?;
 
         match user {
-            Some(user) => return Ok(user),
-            None => return Err(errors::build_unauth_err()),
+            Some(user) => Ok(user),
+            None => Err(ApiError::Unauthorized),
         }
     }
 
-    pub async fn get_user_from_req_with_role(
-        &self,
-        req: &HttpRequest,
-        role: Role,
-    ) -> Result<User, errors::Error> {
-        let user = self.get_user_from_req(req).await;
-        if user.is_err() {
-            return Err(user.unwrap_err());
+    pub async fn create(&self, user_data: CreateUserData) -> Result<User, ApiError> {
+        // checked before slow hashing, so taken email is rejected right away
+        if self.get_by_email(&user_data.email).await?.is_some() {
+            return Err(email_conflict_err());
         }
 
-        let user = user.unwrap();
+        let password_hash = self.auth_service.hash_password(&user_data.password).await?;
+        let user_id = UserId::new();
 
-        if !user.role.satisfy(role) {
-            return Err(errors::build_unauth_err());
+        let new_user = User {
+            _id: user_id,
+            email: user::normalize_email(&user_data.email),
+            role: Role::User,
+            // users register themselves
+            audit: Audit::new(Some(user_id)),
+            deletion: Deletion::default(),
+        };
+
+        let new_user_auth = UserAuth {
+            _id: ObjectId::new(),
+            user_id,
+            password_hash,
+            audit: Audit::new(Some(user_id)),
+        };
+
+        let insert_result = if self.supports_transactions {
+            self.insert_in_transaction(&new_user, &new_user_auth).await
+        } else {
+            self.insert_with_compensation(&new_user, &new_user_auth)
+                .await
+        };
+
+        // unique index still guards against concurrent registrations
+        if let Err(ApiError::Conflict(_)) = insert_result {
+            return Err(email_conflict_err());
         }
 
-        return Ok(user);
-    }
+        insert_result?;
 
-    pub async fn create(&self, user_data: CreateUserData) -> Result<User, errors::Error> {
-        let create_user_result = self
-            .user_collection
-            .insert_one(
-                User {
-                    _id: ObjectId::new().to_hex(),
-                    email: user_data.email,
-                    role: Role::User,
-                },
-                None,
-            )
-            .await;
+        let user = self.get_by_id(&user_id).await?;
 
-        let Ok(result) = create_user_result else {
-            return Err(errors::build_generic_err());
+        let Some(user) = user else {
+            return Err(ApiError::Internal);
         };
 
-        let create_auth_result = self
-            .user_auth_collection
-            .insert_one(
-                UserAuth {
-                    _id: ObjectId::new().to_hex(),
-                    user_id: result.inserted_id.to_string(),
-                    password_hash: self.auth_service.generate_hash(&user_data.password),
-                },
-                None,
-            )
-            .await;
+        Ok(user)
+    }
 
-        if create_auth_result.is_err() {
-            return Err(errors::build_generic_err());
-        }
+    async fn insert_in_transaction(
+        &self,
+        user: &User,
+        user_auth: &UserAuth,
+    ) -> Result<(), ApiError> {
+        db::with_transaction(
+            self.user_collection.client(),
+            (self, user, user_auth),
+            |session, &(this, user, user_auth)| {
+                Box::pin(async move {
+                    this.user_collection
+                        .insert_one_with_session(user, None, session)
+                        .await?;
+
+                    fail_point::check("registration_after_user_insert")?;
+
+                    this.user_auth_collection
+                        .insert_one_with_session(user_auth, None, session)
+                        .await?;
+
+                    Ok(())
+                })
+            },
+        )
+        .await
+    }
+
+    // standalone server has no transactions, so user is deleted back if its auth can't be saved
+    async fn insert_with_compensation(
+        &self,
+        user: &User,
+        user_auth: &UserAuth,
+    ) -> Result<(), ApiError> {
+        self.user_collection.insert_one(user, None).await?;
 
-        let user = self.get_by_id(result.inserted_id.as_str().unwrap()).await;
+        let result = async {
+            fail_point::check("registration_after_user_insert")?;
 
-        if user.is_none() {
-            return Err(errors::build_generic_err());
+            self.user_auth_collection
+                .insert_one(user_auth, None)
+                .await?;
+
+            Ok::<_, ApiError>(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            let delete_result = self
+                .user_collection
+                .delete_one(doc! { "_id": &user._id }, None)
+                .await;
+
+            if let Err(delete_error) = delete_result {
+                println!(
+                    "db: failed to delete user {} after failed registration: {}",
+                    user._id, delete_error
+                );
+            }
+
+            return Err(e);
         }
 
-        return Ok(user.unwrap());
+        Ok(())
     }
 
-    pub async fn login(&self, user_data: CreateUserData) -> Result<User, errors::Error> {
-        let user = self.get_by_email(&user_data.email).await;
+    pub async fn login(&self, user_data: LoginUserData) -> Result<User, ApiError> {
+        let user = self.get_by_email(&user_data.email).await?;
 
         let Some(user) = user else {
-            return Err(errors::build_unauth_err());
+            self.auth_service
+                .verify_dummy_password(&user_data.password)
+                .await;
+            return Err(ApiError::Unauthorized);
         };
 
         let user_auth = self
             .user_auth_collection
             .find_one(
                 doc! {
-                    "password_hash": self.auth_service.generate_hash(user_data.password.as_str()),
-                    "user_id": user._id.clone()
+                    "user_id": user._id
                 },
                 None,
             )
-            .await;
+            .await?;
 
-        let Ok(user_auth) = user_auth else {
-            return Err(errors::build_unauth_err());
+        let Some(user_auth) = user_auth else {
+            self.auth_service
+                .verify_dummy_password(&user_data.password)
+                .await;
+            return Err(ApiError::Unauthorized);
         };
 
-        if user_auth.is_none() {
-            return Err(errors::build_unauth_err());
-        };
+        match self
+            .auth_service
+            .verify_password(&user_data.password, &user_auth.password_hash)
+            .await
+        {
+            PasswordCheck::Valid => {}
+            PasswordCheck::ValidNeedsRehash => {
+                self.rehash_password(&user_auth, &user_data.password).await
+            }
+            PasswordCheck::Invalid => return Err(ApiError::Unauthorized),
+        }
 
-        return Ok(user);
+        Ok(user)
     }
 
-    pub async fn list(&self) -> Result<Vec<User>, DbError> {
-        let find_result = self.user_collection.find(None, None).await;
-        if find_result.is_err() {
-            return Err(find_result.unwrap_err());
+    // upgrade of stored hash should never break successful login, so errors are only logged
+    async fn rehash_password(&self, user_auth: &UserAuth, password: &str) {
+        let Ok(password_hash) = self.auth_service.hash_password(password).await else {
+            println!(
+                "auth: failed to rehash password for user_auth {}",
+                user_auth._id
+            );
+            return;
         };
 
-        let users = find_result
-            .unwrap()
-            .try_collect()
-            .unwrap_or_else(|_e| vec![])
+        let update_result = self
+            .user_auth_collection
+            .update_one(
+                doc! {
+                    "_id": user_auth._id
+                },
+                audit::set_with_audit(
+                    doc! { "password_hash": password_hash },
+                    Some(user_auth.user_id),
+                ),
+                None,
+            )
             .await;
-        return Ok(users);
+
+        if let Err(e) = update_result {
+            println!(
+                "auth: failed to store rehashed password for user_auth {}: {}",
+                user_auth._id, e
+            );
+        }
+    }
+
+    pub async fn list(&self, query: UserListQuery) -> Result<Page<User>, ApiError> {
+        self.find_page(query, deletion::not_deleted()).await
     }
 
-    pub async fn get_by_id(&self, id: &str) -> Option<User> {
-        let find_result = self
+    pub async fn list_deleted(&self, query: UserListQuery) -> Result<Page<User>, ApiError> {
+        self.find_page(query, deletion::deleted()).await
+    }
+
+    async fn find_page(
+        &self,
+        query: UserListQuery,
+        deletion_condition: Document,
+    ) -> Result<Page<User>, ApiError> {
+        let page = PageRequest::new(query.limit, query.cursor.as_deref(), query.sort)?;
+
+        let mut conditions = vec![deletion_condition];
+
+        if let Some(role) = query.role {
+            conditions.push(doc! { "role": role });
+        }
+
+        if let Some(created_after) = query.created_after {
+            conditions.push(doc! { "created_at": { "$gte": created_after } });
+        }
+
+        conditions.extend(page.cursor_condition());
+
+        let users: Vec<User> = self
             .user_collection
+            .find(pagination::and_filter(conditions), page.find_options())
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(page.into_page(users, |user| user._id.into()))
+    }
+
+    pub async fn get_by_id(&self, id: &UserId) -> Result<Option<User>, DbError> {
+        self.user_collection
             .find_one(
                 doc! {
-                    "_id": id
+                    "_id": id,
+                    "deleted_at": Bson::Null,
                 },
                 None,
             )
-            .await;
+            .await
+    }
+
+    // deleted user can't sign in and all issued tokens are revoked
+    pub async fn delete(&self, id: &UserId, admin: &User) -> Result<(), ApiError> {
+        let result = self
+            .user_collection
+            .update_one(
+                doc! { "_id": id, "deleted_at": Bson::Null },
+                deletion::mark_deleted(admin._id),
+                None,
+            )
+            .await?;
+
+        if result.matched_count == 0 {
+            return Err(ApiError::NotFound);
+        }
 
-        return find_result.unwrap_or_else(|_e| None);
+        self.token_service.revoke_all(id).await
     }
 
-    pub async fn get_by_email(&self, email: &str) -> Option<User> {
-        let find_result = self
+    pub async fn restore(&self, id: &UserId, admin: &User) -> Result<User, ApiError> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let restored_user = self
             .user_collection
+            .find_one_and_update(
+                doc! { "_id": id, "deleted_at": { "$ne": Bson::Null } },
+                deletion::mark_restored(admin._id),
+                options,
+            )
+            .await?;
+
+        restored_user.ok_or(ApiError::NotFound)
+    }
+
+    pub async fn get_by_email(&self, email: &str) -> Result<Option<User>, DbError> {
+        let options = FindOneOptions::builder()
+            .collation(user::email_collation())
+            .build();
+
+        self.user_collection
             .find_one(
                 doc! {
-                    "email": email
+                    "email": user::normalize_email(email),
+                    "deleted_at": Bson::Null,
                 },
-                None,
+                options,
             )
-            .await;
-
-        return find_result.unwrap_or_else(|_e| None);
+            .await
     }
 
-    pub fn new(db: Rc<Database>, auth_service: Rc<AuthService>) -> Self {
-        let user_collection: Collection<User> = db.collection("users");
-        let user_auth_collection: Collection<UserAuth> = db.collection("user_auths");
+    pub fn new(
+        db: Rc<Database>,
+        auth_service: Rc<AuthService>,
+        token_service: Rc<TokenService>,
+        supports_transactions: bool,
+    ) -> Self {
+        let user_collection: Collection<User> = db.collection(User::COLLECTION_NAME);
+        let user_auth_collection: Collection<UserAuth> = db.collection(UserAuth::COLLECTION_NAME);
         UserService {
             db,
             auth_service,
+            token_service,
+            supports_transactions,
             user_collection,
             user_auth_collection,
         }
     }
 }
+
+fn email_conflict_err() -> ApiError {
+    ApiError::Conflict("Email is already registered".to_string())
+}
@@ -0,0 +1,31 @@
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineChange {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffLine {
+    pub change: LineChange,
+    pub text: String,
+}
+
+// line by line difference, unchanged lines are included so text can be shown as a whole
+pub fn lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            change: match change.tag() {
+                ChangeTag::Equal => LineChange::Equal,
+                ChangeTag::Insert => LineChange::Insert,
+                ChangeTag::Delete => LineChange::Delete,
+            },
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect()
+}
@@ -1,10 +1,152 @@
-use actix_web::error;
-pub use actix_web::error::Error;
+use std::fmt;
 
-pub fn build_unauth_err() -> Error {
-    error::ErrorUnauthorized("Not authorized")
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use jsonwebtoken::errors::ErrorKind as JwtErrorKind;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::{
+    db::{self, TransactionError},
+    DbError,
+};
+
+// every error leaving the api is serialized as ErrorBody,
+// `code` values are part of api contract and should never be renamed
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized,
+    TokenExpired,
+    Forbidden,
+    NotFound,
+    Conflict(String),
+    PayloadTooLarge,
+    Validation(Value),
+    Db(DbError),
+    Internal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorBody<'a> {
+    pub code: &'a str,
+    pub message: String,
+    pub details: Option<&'a Value>,
+    pub request_id: Option<&'a str>,
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::TokenExpired => "token_expired",
+            ApiError::Forbidden => "forbidden",
+            ApiError::NotFound => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::PayloadTooLarge => "payload_too_large",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::Db(_) => "database_error",
+            ApiError::Internal => "internal_error",
+        }
+    }
+
+    pub fn to_response(&self, request_id: Option<&str>) -> HttpResponse {
+        let details = match self {
+            ApiError::Validation(details) => Some(details),
+            _ => None,
+        };
+
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            details,
+            request_id,
+        })
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(message) => write!(f, "{}", message),
+            ApiError::Unauthorized => write!(f, "Not authorized"),
+            ApiError::TokenExpired => write!(f, "Token expired"),
+            ApiError::Forbidden => write!(f, "Forbidden"),
+            ApiError::NotFound => write!(f, "Not found"),
+            ApiError::Conflict(message) => write!(f, "{}", message),
+            ApiError::PayloadTooLarge => write!(f, "Payload too large"),
+            ApiError::Validation(_) => write!(f, "Validation failed"),
+            // database details are logged, but never sent to client
+            ApiError::Db(_) => write!(f, "Database error"),
+            ApiError::Internal => write!(f, "Internal error"),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized | ApiError::TokenExpired => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Db(_) | ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.to_response(None)
+    }
+}
+
+impl From<DbError> for ApiError {
+    fn from(e: DbError) -> Self {
+        if db::is_duplicate_key_error(&e) {
+            return ApiError::Conflict("Duplicate value".to_string());
+        }
+
+        println!("db: {}", e);
+        ApiError::Db(e)
+    }
+}
+
+impl TransactionError for ApiError {
+    fn is_transient(&self) -> bool {
+        matches!(self, ApiError::Db(e) if e.is_transient())
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for ApiError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        match e.kind() {
+            JwtErrorKind::ExpiredSignature => ApiError::TokenExpired,
+            _ => ApiError::Unauthorized,
+        }
+    }
+}
+
+// error handlers for built-in extractors, so malformed input also gets ErrorBody
+
+pub fn json_error_handler(e: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match e {
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+            ApiError::PayloadTooLarge.into()
+        }
+        _ => ApiError::BadRequest(e.to_string()).into(),
+    }
+}
+
+pub fn path_error_handler(e: PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(e.to_string()).into()
 }
 
-pub fn build_generic_err() -> Error {
-    error::ErrorInternalServerError("Internal error")
+pub fn query_error_handler(e: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(e.to_string()).into()
 }
@@ -0,0 +1,11 @@
+use crate::{config, utils::errors::ApiError};
+
+// lets integration tests break a flow at a named point by starting api with FAIL_POINT=<name>
+pub fn check(name: &str) -> Result<(), ApiError> {
+    if config::get().api.fail_point.as_deref() == Some(name) {
+        println!("fail_point: \"{}\" triggered", name);
+        return Err(ApiError::Internal);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+// bytes of text kept around each match
+const SNIPPET_CONTEXT: usize = 40;
+const MAX_SNIPPETS: usize = 3;
+
+// words of text search query, excluding negated ones;
+// matching is ascii case-insensitive, so stemmed forms found by mongo may be not highlighted
+pub fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .filter(|word| !word.starts_with('-'))
+        .map(|word| word.trim_matches('"').to_ascii_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// fragments of text around matched terms with matches wrapped in <mark>,
+// the rest of text is html-escaped, so snippets are safe to render as is
+pub fn snippets(text: &str, terms: &[String]) -> Vec<String> {
+    let matches = find_matches(text, terms);
+    let mut snippets = vec![];
+    let mut covered_until = 0;
+
+    for (index, range) in matches.iter().enumerate() {
+        if range.start < covered_until {
+            continue;
+        }
+
+        if snippets.len() == MAX_SNIPPETS {
+            break;
+        }
+
+        let start = floor_char_boundary(text, range.start.saturating_sub(SNIPPET_CONTEXT));
+        let window_end = ceil_char_boundary(text, range.end + SNIPPET_CONTEXT);
+        // match crossing the window edge is kept whole, so it's highlighted too
+        let end = matches[index..]
+            .iter()
+            .take_while(|m| m.start < window_end)
+            .last()
+            .map_or(window_end, |m| m.end.max(window_end));
+
+        let mut snippet = String::new();
+        let mut position = start;
+
+        if start > 0 {
+            snippet.push('…');
+        }
+
+        for range in matches[index..].iter().take_while(|m| m.end <= end) {
+            snippet.push_str(&escape(&text[position..range.start]));
+            snippet.push_str("<mark>");
+            snippet.push_str(&escape(&text[range.clone()]));
+            snippet.push_str("</mark>");
+            position = range.end;
+        }
+
+        snippet.push_str(&escape(&text[position..end]));
+
+        if end < text.len() {
+            snippet.push('…');
+        }
+
+        snippets.push(snippet);
+        covered_until = end;
+    }
+
+    snippets
+}
+
+// sorted, not overlapping byte ranges of terms in text
+fn find_matches(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    // ascii lowercasing keeps byte offsets the same as in original text
+    let lowercase_text = text.to_ascii_lowercase();
+
+    let mut matches: Vec<Range<usize>> = terms
+        .iter()
+        .flat_map(|term| {
+            lowercase_text
+                .match_indices(term.as_str())
+                .map(|(start, term)| start..start + term.len())
+        })
+        .collect();
+
+    matches.sort_by_key(|range| (range.start, usize::MAX - range.end));
+
+    let mut merged: Vec<Range<usize>> = vec![];
+
+    for range in matches {
+        match merged.last_mut() {
+            Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+
+    index
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
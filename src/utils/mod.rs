@@ -1 +1,6 @@
+pub mod diff;
 pub mod errors;
+pub mod fail_point;
+pub mod highlight;
+pub mod pagination;
+pub mod trim;
@@ -0,0 +1,202 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    options::FindOptions,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::errors::ApiError;
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+// keyset pagination over _id, which is ordered by creation time;
+// cursor is opaque for clients, but is just base64 of the last returned _id
+#[derive(Debug)]
+pub struct PageRequest {
+    limit: i64,
+    after: Option<ObjectId>,
+    sort: SortOrder,
+}
+
+impl PageRequest {
+    pub fn new(
+        limit: Option<i64>,
+        cursor: Option<&str>,
+        sort: Option<SortOrder>,
+    ) -> Result<Self, ApiError> {
+        let after = match cursor {
+            Some(cursor) => Some(ObjectId::from_bytes(decode_cursor(cursor)?)),
+            None => None,
+        };
+
+        Ok(PageRequest {
+            limit: validate_limit(limit)?,
+            after,
+            sort: sort.unwrap_or_default(),
+        })
+    }
+
+    // condition to combine with other filters using $and
+    pub fn cursor_condition(&self) -> Option<Document> {
+        let after = self.after?;
+
+        Some(match self.sort {
+            SortOrder::Newest => doc! { "_id": { "$lt": after } },
+            SortOrder::Oldest => doc! { "_id": { "$gt": after } },
+        })
+    }
+
+    // one extra document is requested to know whether next page exists
+    pub fn find_options(&self) -> FindOptions {
+        let direction = match self.sort {
+            SortOrder::Newest => -1,
+            SortOrder::Oldest => 1,
+        };
+
+        FindOptions::builder()
+            .sort(doc! { "_id": direction })
+            .limit(self.limit + 1)
+            .build()
+    }
+
+    pub fn into_page<T>(self, mut items: Vec<T>, id_of: impl Fn(&T) -> ObjectId) -> Page<T> {
+        let has_more = items.len() as i64 > self.limit;
+        items.truncate(self.limit as usize);
+
+        let next_cursor = match items.last() {
+            Some(last) if has_more => Some(URL_SAFE_NO_PAD.encode(id_of(last).bytes())),
+            _ => None,
+        };
+
+        Page { items, next_cursor }
+    }
+}
+
+// text search results are ordered by relevance,
+// so cursor holds both score and _id of the last returned item
+#[derive(Debug)]
+pub struct ScoredPageRequest {
+    limit: i64,
+    after: Option<(f64, ObjectId)>,
+}
+
+impl ScoredPageRequest {
+    pub fn new(limit: Option<i64>, cursor: Option<&str>) -> Result<Self, ApiError> {
+        let after = match cursor {
+            Some(cursor) => {
+                let bytes: [u8; 20] = decode_cursor(cursor)?;
+
+                let mut score = [0u8; 8];
+                let mut id = [0u8; 12];
+                score.copy_from_slice(&bytes[..8]);
+                id.copy_from_slice(&bytes[8..]);
+
+                Some((f64::from_be_bytes(score), ObjectId::from_bytes(id)))
+            }
+            None => None,
+        };
+
+        Ok(ScoredPageRequest {
+            limit: validate_limit(limit)?,
+            after,
+        })
+    }
+
+    // to be used after score is added to documents as `score` field
+    pub fn cursor_condition(&self) -> Option<Document> {
+        let (score, id) = self.after?;
+
+        Some(doc! {
+            "$or": [
+                { "score": { "$lt": score } },
+                { "score": score, "_id": { "$lt": id } },
+            ]
+        })
+    }
+
+    pub fn sort(&self) -> Document {
+        doc! { "score": -1, "_id": -1 }
+    }
+
+    // one extra document is requested to know whether next page exists
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+
+    pub fn into_page<T>(
+        self,
+        mut items: Vec<T>,
+        key_of: impl Fn(&T) -> (f64, ObjectId),
+    ) -> Page<T> {
+        let has_more = items.len() as i64 > self.limit;
+        items.truncate(self.limit as usize);
+
+        let next_cursor = match items.last() {
+            Some(last) if has_more => {
+                let (score, id) = key_of(last);
+                let mut bytes = score.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&id.bytes());
+
+                Some(URL_SAFE_NO_PAD.encode(bytes))
+            }
+            _ => None,
+        };
+
+        Page { items, next_cursor }
+    }
+}
+
+pub fn and_filter(conditions: Vec<Document>) -> Document {
+    if conditions.is_empty() {
+        return doc! {};
+    }
+
+    doc! { "$and": conditions }
+}
+
+pub fn validate_limit(limit: Option<i64>) -> Result<i64, ApiError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::BadRequest(format!(
+            "limit should be between 1 and {}",
+            MAX_LIMIT
+        )));
+    }
+
+    Ok(limit)
+}
+
+fn decode_cursor<const N: usize>(cursor: &str) -> Result<[u8; N], ApiError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| <[u8; N]>::try_from(bytes).ok());
+
+    bytes.ok_or_else(|| ApiError::BadRequest("cursor is invalid".to_string()))
+}
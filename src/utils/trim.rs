@@ -0,0 +1,19 @@
+use serde::{Deserialize, Deserializer};
+
+// use with #[serde(deserialize_with = "trim::trimmed")] to strip surrounding whitespace before validation
+pub fn trimmed<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Ok(value.trim().to_string())
+}
+
+// same for optional fields, use together with #[serde(default)]
+pub fn trimmed_option<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|value| value.trim().to_string()))
+}
@@ -1,6 +1,7 @@
 import t from "node:test";
 import assert from "node:assert";
 import api from "./api.js";
+import { bootstrap, shutdown } from "./bootstrap.js";
 import { registerUser } from "./helpers/user.js";
 
 export function unauthorized({ method, url, data }) {
@@ -51,7 +52,31 @@ export async function unauthorizedForRole({ method, url, data, role, token }) {
   );
 }
 
+const topologies = [
+  { name: "standalone", replSet: false },
+  { name: "replica set", replSet: true },
+];
+
+// declares the same tests against standalone server and replica set,
+// services take different paths there, since only replica set has transactions
+export function eachTopology(title, options, fn) {
+  if (typeof options === "function") {
+    fn = options;
+    options = {};
+  }
+
+  for (const { name, replSet } of topologies) {
+    t.describe(`${title} on ${name}`, () => {
+      t.before(async () => await bootstrap({ ...options, replSet }));
+      t.after(async () => await shutdown());
+
+      fn({ replSet });
+    });
+  }
+}
+
 export default {
   unauthorized,
   unauthorizedForRole,
+  eachTopology,
 };
@@ -35,7 +35,7 @@ class AxiosSimpleError extends Error {
   }
 }
 
-export async function startApi({ mongourl, port }) {
+export async function startApi({ mongourl, port, env = {} }) {
   apiProcess = await spawn({
     command: `cargo run --quiet`,
     args: [],
@@ -46,6 +46,7 @@ export async function startApi({ mongourl, port }) {
         MONGODB_URI: mongourl,
         PORT: port,
         THREAD_COUNT: "2",
+        ...env,
       },
     },
     waitForOutput: "db: connected.",
@@ -14,11 +14,12 @@ function _getFreePort() {
   });
 }
 
-export async function bootstrap() {
-  await mongo.createMongo();
+export async function bootstrap({ env, replSet } = {}) {
+  await mongo.createMongo({ replSet });
   await api.startApi({
     mongourl: mongo.getUrl(),
     port: await _getFreePort(),
+    env,
   });
 }
 
@@ -0,0 +1,36 @@
+import cp from "child_process";
+import mongo from "./mongo.js";
+
+/**
+ * Runs one-off maintenance command against test database
+ * @param {string} command
+ * @param {string[]} args
+ * @param {Object} env
+ * @returns {Promise<string>} command output
+ */
+export function runCommand(command, args = [], env = {}) {
+  console.log(`command: ${command}`);
+
+  return new Promise((resolve, reject) => {
+    cp.exec(
+      `cargo run --quiet -- ${[command, ...args].join(" ")}`,
+      {
+        cwd: process.cwd(),
+        env: {
+          ...process.env,
+          MONGODB_URI: mongo.getUrl(),
+          ...env,
+        },
+      },
+      (error, stdout, stderr) => {
+        if (stderr) console.error(stderr);
+        if (error) return reject(error);
+        resolve(stdout);
+      },
+    );
+  });
+}
+
+export default {
+  runCommand,
+};
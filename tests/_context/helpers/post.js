@@ -0,0 +1,13 @@
+import { getApi } from "../api.js";
+
+export function createPost(token, data = {}) {
+  return getApi({ token }).post("/posts", {
+    title: "Some title",
+    content: "Some content",
+    ...data,
+  });
+}
+
+export default {
+  createPost,
+};
@@ -1,3 +1,4 @@
+import { ObjectId } from "mongodb";
 import mongo from "../mongo.js";
 import { v4 as uuid } from "uuid";
 import { getApi } from "../api.js";
@@ -20,7 +21,7 @@ export async function registerUser({ email, password, role } = {}) {
     await collection.find({});
     await collection.findOneAndUpdate(
       {
-        _id: registerData.user._id,
+        _id: new ObjectId(registerData.user._id),
       },
       {
         $set: {
@@ -1,5 +1,8 @@
 import { getApi } from "./api.js";
 import { bootstrap, shutdown } from "./bootstrap.js";
+import command from "./command.js";
+import mongo from "./mongo.js";
+import post from "./helpers/post.js";
 import user from "./helpers/user.js";
 import test from "./addtionalTesters.js";
 
@@ -7,6 +10,9 @@ export default {
   api: getApi,
   bootstrap,
   shutdown,
+  command,
+  mongo,
+  post,
   user,
   test,
 };
@@ -1,12 +1,15 @@
 import { MongoClient, Db } from "mongodb";
-import { MongoMemoryServer } from "mongodb-memory-server";
+import { MongoMemoryReplSet, MongoMemoryServer } from "mongodb-memory-server";
 
 let mongoServer = null;
 let mongourl = null;
 let mongoClientPromise = null;
 
-export async function createMongo() {
-  mongoServer = await MongoMemoryServer.create();
+export async function createMongo({ replSet = false } = {}) {
+  // single-node replica set is required for transactions
+  mongoServer = replSet
+    ? await MongoMemoryReplSet.create({ replSet: { count: 1 } })
+    : await MongoMemoryServer.create();
   mongourl = mongoServer.getUri();
 
   console.log(`mongo: created with url "${mongourl}"`);
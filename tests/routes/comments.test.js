@@ -0,0 +1,160 @@
+import test from "node:test";
+import assert from "node:assert";
+import context from "../_context/index.js";
+
+test.describe("/posts/:id/comments", () => {
+  let authorData;
+  let commenterData;
+  let post;
+
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.before(async () => {
+    authorData = await context.user.registerUser();
+    commenterData = await context.user.registerUser();
+
+    const result = await context.post.createPost(authorData.token);
+    post = result.data;
+  });
+
+  async function getPost() {
+    const result = await context.api().get(`/posts/${post._id}`);
+    return result.data;
+  }
+
+  function createComment(data, token = commenterData.token) {
+    return context
+      .api({ token })
+      .post(`/posts/${post._id}/comments`, data);
+  }
+
+  context.test.unauthorized({
+    url: "/posts/000000000000000000000000/comments",
+    method: "post",
+    data: { content: "Some comment" },
+  });
+
+  test.it("should return 404 for missing post", async () => {
+    const error = await context
+      .api()
+      .get("/posts/000000000000000000000000/comments")
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+  });
+
+  test.it("should create comment and count it on post", async () => {
+    const before = await getPost();
+    const result = await createComment({ content: "  First!  " });
+
+    assert.equal(result.data.content, "First!");
+    assert.equal(result.data.user_id, commenterData.user._id);
+    assert.equal(result.data.parent_id, null);
+
+    const after = await getPost();
+    assert.equal(after.comment_count, before.comment_count + 1);
+
+    const list = await context.api().get(`/posts/${post._id}/comments`);
+    assert.ok(list.data.items.some((c) => c._id === result.data._id));
+  });
+
+  test.it("should reject empty comment", async () => {
+    const error = await createComment({ content: "   " }).catch((e) => e);
+
+    assert.equal(error.status, 422);
+    assert.ok(error.response.data.details.content);
+  });
+
+  test.it("should list replies by parent", async () => {
+    const parent = await createComment({ content: "Parent" });
+    const reply = await createComment(
+      { content: "Reply", parent_id: parent.data._id },
+      authorData.token,
+    );
+
+    assert.deepEqual(reply.data.ancestors, [parent.data._id]);
+
+    const topLevel = await context.api().get(`/posts/${post._id}/comments`);
+    assert.ok(!topLevel.data.items.some((c) => c._id === reply.data._id));
+    assert.equal(
+      topLevel.data.items.find((c) => c._id === parent.data._id).reply_count,
+      1,
+    );
+
+    const replies = await context
+      .api()
+      .get(`/posts/${post._id}/comments`, {
+        params: { parent: parent.data._id },
+      });
+    assert.deepEqual(
+      replies.data.items.map((c) => c._id),
+      [reply.data._id],
+    );
+  });
+
+  test.it("should reject replies nested too deep", async () => {
+    let parentId = null;
+
+    for (let depth = 0; depth < 5; depth++) {
+      const result = await createComment({
+        content: `Level ${depth}`,
+        parent_id: parentId,
+      });
+      parentId = result.data._id;
+    }
+
+    const error = await createComment({
+      content: "Too deep",
+      parent_id: parentId,
+    }).catch((e) => e);
+
+    assert.equal(error.status, 400);
+  });
+
+  test.it("should allow edit only for comment author", async () => {
+    const comment = await createComment({ content: "Original" });
+    const url = `/posts/${post._id}/comments/${comment.data._id}`;
+
+    const error = await context
+      .api({ token: authorData.token })
+      .patch(url, { content: "Changed by post author" })
+      .catch((e) => e);
+    assert.equal(error.status, 403);
+
+    const result = await context
+      .api({ token: commenterData.token })
+      .patch(url, { content: "Changed" });
+    assert.equal(result.data.content, "Changed");
+    assert.equal(result.data.updated_by, commenterData.user._id);
+  });
+
+  test.it("should delete comment with its replies", async () => {
+    const parent = await createComment({ content: "Parent" });
+    await createComment({ content: "Reply", parent_id: parent.data._id });
+    const before = await getPost();
+
+    const otherUserData = await context.user.registerUser();
+    const url = `/posts/${post._id}/comments/${parent.data._id}`;
+
+    const error = await context
+      .api({ token: otherUserData.token })
+      .delete(url)
+      .catch((e) => e);
+    assert.equal(error.status, 403);
+
+    // post author moderates comments of their post
+    const result = await context.api({ token: authorData.token }).delete(url);
+    assert.equal(result.status, 204);
+
+    const after = await getPost();
+    assert.equal(after.comment_count, before.comment_count - 2);
+
+    const replies = await context
+      .api()
+      .get(`/posts/${post._id}/comments`, {
+        params: { parent: parent.data._id },
+      });
+    assert.deepEqual(replies.data.items, []);
+  });
+});
@@ -0,0 +1,63 @@
+import test from "node:test";
+import assert from "node:assert";
+import context from "../_context/index.js";
+
+test.describe("errors", () => {
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.it("unknown route should return not_found error body", async () => {
+    const error = await context
+      .api()
+      .get("/not-existing-route")
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+    assert.equal(error.response.data.code, "not_found");
+    assert.equal(typeof error.response.data.message, "string");
+    assert.equal(error.response.data.details, null);
+    assert.equal(typeof error.response.data.request_id, "string");
+  });
+
+  test.it("unauthorized request should return unauthorized code", async () => {
+    const error = await context
+      .api()
+      .get("/users/me")
+      .catch((e) => e);
+
+    assert.equal(error.status, 401);
+    assert.equal(error.response.data.code, "unauthorized");
+  });
+
+  test.it("malformed json should return bad_request code", async () => {
+    const error = await context
+      .api()
+      .post("/users/login", "{not json", {
+        headers: { "Content-Type": "application/json" },
+      })
+      .catch((e) => e);
+
+    assert.equal(error.status, 400);
+    assert.equal(error.response.data.code, "bad_request");
+  });
+
+  test.it("oversized json should return payload_too_large code", async () => {
+    const error = await context
+      .api()
+      .post("/users/login", { email: "a".repeat(1024 * 1024), password: "" })
+      .catch((e) => e);
+
+    assert.equal(error.status, 413);
+    assert.equal(error.response.data.code, "payload_too_large");
+  });
+
+  test.it("should echo x-request-id in header and error body", async () => {
+    const error = await context
+      .api()
+      .get("/users/me", { headers: { "x-request-id": "test-request-id" } })
+      .catch((e) => e);
+
+    assert.equal(error.response.headers["x-request-id"], "test-request-id");
+    assert.equal(error.response.data.request_id, "test-request-id");
+  });
+});
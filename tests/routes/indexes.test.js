@@ -0,0 +1,35 @@
+import test from "node:test";
+import assert from "node:assert";
+import context from "../_context/index.js";
+
+test.describe("indexes", () => {
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  async function getIndexes(collectionName) {
+    const db = await context.mongo.getDatabase();
+    return db.collection(collectionName).indexes();
+  }
+
+  test.it("users should have unique email index", async () => {
+    const indexes = await getIndexes("users");
+    const emailIndex = indexes.find((i) => i.name === "email_1");
+
+    assert.ok(emailIndex);
+    assert.equal(emailIndex.unique, true);
+  });
+
+  test.it("user_auths should have unique user_id index", async () => {
+    const indexes = await getIndexes("user_auths");
+    const userIdIndex = indexes.find((i) => i.name === "user_id_1");
+
+    assert.ok(userIdIndex);
+    assert.equal(userIdIndex.unique, true);
+  });
+
+  test.it("posts should have user_id index", async () => {
+    const indexes = await getIndexes("posts");
+
+    assert.ok(indexes.some((i) => i.name === "user_id_1"));
+  });
+});
@@ -0,0 +1,110 @@
+import test from "node:test";
+import assert from "node:assert";
+import crypto from "node:crypto";
+import fs from "node:fs";
+import os from "node:os";
+import path from "node:path";
+import context from "../_context/index.js";
+
+function writeKeyPair(dir, name) {
+  const { publicKey, privateKey } = crypto.generateKeyPairSync("rsa", {
+    modulusLength: 2048,
+    publicKeyEncoding: { type: "spki", format: "pem" },
+    privateKeyEncoding: { type: "pkcs8", format: "pem" },
+  });
+
+  const publicKeyPath = path.join(dir, `${name}.pub.pem`);
+  const privateKeyPath = path.join(dir, `${name}.pem`);
+  fs.writeFileSync(publicKeyPath, publicKey);
+  fs.writeFileSync(privateKeyPath, privateKey);
+
+  return { publicKeyPath, privateKeyPath };
+}
+
+function decodePart(part) {
+  return JSON.parse(Buffer.from(part, "base64url").toString());
+}
+
+test.describe("/.well-known", () => {
+  const keysDir = fs.mkdtempSync(path.join(os.tmpdir(), "jwt-keys-"));
+  const currentKey = writeKeyPair(keysDir, "current");
+  const previousKey = writeKeyPair(keysDir, "previous");
+
+  test.before(
+    async () =>
+      await context.bootstrap({
+        env: {
+          JWT_ALGORITHM: "RS256",
+          JWT_KEY_ID: "current",
+          JWT_PRIVATE_KEY_PATH: currentKey.privateKeyPath,
+          JWT_PUBLIC_KEYS: `current=${currentKey.publicKeyPath},previous=${previousKey.publicKeyPath}`,
+        },
+      }),
+  );
+  test.after(async () => {
+    await context.shutdown();
+    fs.rmSync(keysDir, { recursive: true, force: true });
+  });
+
+  test.it("get /jwks.json should return all verification keys", async () => {
+    const result = await context.api().get("/.well-known/jwks.json");
+
+    const kids = result.data.keys.map((k) => k.kid).sort();
+    assert.deepEqual(kids, ["current", "previous"]);
+    result.data.keys.forEach((k) => {
+      assert.equal(k.kty, "RSA");
+      assert.equal(k.alg, "RS256");
+      assert.ok(!k.d);
+    });
+  });
+
+  test.it("issued token should be verifiable with jwks", async () => {
+    const { token } = await context.user.registerUser();
+    const [header, payload, signature] = token.split(".");
+
+    assert.equal(decodePart(header).alg, "RS256");
+    assert.equal(decodePart(header).kid, "current");
+
+    const jwks = (await context.api().get("/.well-known/jwks.json")).data;
+    const jwk = jwks.keys.find((k) => k.kid === "current");
+    const publicKey = crypto.createPublicKey({ key: jwk, format: "jwk" });
+
+    const isValid = crypto.verify(
+      "sha256",
+      Buffer.from(`${header}.${payload}`),
+      publicKey,
+      Buffer.from(signature, "base64url"),
+    );
+    assert.ok(isValid);
+  });
+
+  test.it("token signed with previous key should be accepted", async () => {
+    const { user } = await context.user.registerUser();
+    const now = Math.floor(Date.now() / 1000);
+
+    const header = Buffer.from(
+      JSON.stringify({ alg: "RS256", typ: "JWT", kid: "previous" }),
+    ).toString("base64url");
+    const payload = Buffer.from(
+      JSON.stringify({
+        user_id: user._id,
+        jti: crypto.randomUUID(),
+        iat: now,
+        exp: now + 60,
+      }),
+    ).toString("base64url");
+    const signature = crypto
+      .sign(
+        "sha256",
+        Buffer.from(`${header}.${payload}`),
+        fs.readFileSync(previousKey.privateKeyPath),
+      )
+      .toString("base64url");
+
+    const result = await context
+      .api({ token: `${header}.${payload}.${signature}` })
+      .get("/users/me");
+
+    assert.equal(result.data._id, user._id);
+  });
+});
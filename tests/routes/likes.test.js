@@ -0,0 +1,129 @@
+import test from "node:test";
+import assert from "node:assert";
+import { ObjectId } from "mongodb";
+import context from "../_context/index.js";
+
+test.describe("/posts/:id/like", () => {
+  let authorData;
+  let likerData;
+  let post;
+
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.before(async () => {
+    authorData = await context.user.registerUser();
+    likerData = await context.user.registerUser();
+
+    const result = await context.post.createPost(authorData.token);
+    post = result.data;
+  });
+
+  context.test.unauthorized({
+    url: "/posts/000000000000000000000000/like",
+    method: "put",
+  });
+
+  test.it("put should return 404 for missing post", async () => {
+    const error = await context
+      .api({ token: likerData.token })
+      .put("/posts/000000000000000000000000/like")
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+  });
+
+  test.it("put should count like only once", async () => {
+    const api = context.api({ token: likerData.token });
+
+    const first = await api.put(`/posts/${post._id}/like`);
+    assert.deepEqual(first.data, { like_count: 1, liked_by_me: true });
+
+    const second = await api.put(`/posts/${post._id}/like`);
+    assert.deepEqual(second.data, { like_count: 1, liked_by_me: true });
+
+    const stored = await context.api().get(`/posts/${post._id}`);
+    assert.equal(stored.data.like_count, 1);
+  });
+
+  test.it("get /posts should show liked_by_me for authenticated user", async () => {
+    const anonymous = await context.api().get("/posts");
+    assert.equal(anonymous.data.items[0].liked_by_me, undefined);
+
+    const liker = await context.api({ token: likerData.token }).get("/posts");
+    assert.equal(liker.data.items[0].liked_by_me, true);
+
+    const author = await context.api({ token: authorData.token }).get("/posts");
+    assert.equal(author.data.items[0].liked_by_me, false);
+  });
+
+  test.it("get /posts should reject invalid token", async () => {
+    const error = await context
+      .api({ token: "invalid" })
+      .get("/posts")
+      .catch((e) => e);
+
+    assert.equal(error.status, 401);
+  });
+
+  test.it("delete should remove like once", async () => {
+    const api = context.api({ token: likerData.token });
+
+    const first = await api.delete(`/posts/${post._id}/like`);
+    assert.deepEqual(first.data, { like_count: 0, liked_by_me: false });
+
+    const second = await api.delete(`/posts/${post._id}/like`);
+    assert.deepEqual(second.data, { like_count: 0, liked_by_me: false });
+  });
+
+  test.it("concurrent likes should all be counted", async () => {
+    const users = await Promise.all(
+      [1, 2, 3, 4, 5].map(() => context.user.registerUser()),
+    );
+
+    await Promise.all(
+      users.map((user) =>
+        context.api({ token: user.token }).put(`/posts/${post._id}/like`),
+      ),
+    );
+
+    const stored = await context.api().get(`/posts/${post._id}`);
+    assert.equal(stored.data.like_count, 5);
+  });
+
+  test.it("repair-like-counts should recount drifted like_count", async () => {
+    const db = await context.mongo.getDatabase();
+    await db
+      .collection("posts")
+      .updateOne({ _id: new ObjectId(post._id) }, { $set: { like_count: 7 } });
+
+    const output = await context.command.runCommand("repair-like-counts");
+    assert.ok(output.includes("repaired 1"));
+
+    const stored = await context.api().get(`/posts/${post._id}`);
+    assert.equal(stored.data.like_count, 5);
+  });
+});
+
+context.test.eachTopology("like_count", () => {
+  test.it("should match stored likes after repeated like and unlike", async () => {
+    const authorData = await context.user.registerUser();
+    const likerData = await context.user.registerUser();
+    const api = context.api({ token: likerData.token });
+
+    const result = await context.post.createPost(authorData.token);
+    const post = result.data;
+
+    await Promise.all([
+      api.put(`/posts/${post._id}/like`),
+      api.put(`/posts/${post._id}/like`),
+      context.api({ token: authorData.token }).put(`/posts/${post._id}/like`),
+    ]);
+
+    const liked = await context.api().get(`/posts/${post._id}`);
+    assert.equal(liked.data.like_count, 2);
+
+    const unliked = await api.delete(`/posts/${post._id}/like`);
+    assert.deepEqual(unliked.data, { like_count: 1, liked_by_me: false });
+  });
+});
@@ -0,0 +1,64 @@
+import test from "node:test";
+import assert from "node:assert";
+import { ObjectId } from "mongodb";
+import context from "../_context/index.js";
+
+test.describe("migrations", () => {
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.it("should be applied on startup", async () => {
+    const db = await context.mongo.getDatabase();
+
+    const applied = await db.collection("migrations").find({}).toArray();
+
+    assert.ok(applied.some((m) => m._id === 1 && m.name === "create_collections"));
+  });
+
+  test.it("should release lock after applying", async () => {
+    const db = await context.mongo.getDatabase();
+
+    assert.equal(await db.collection("migration_locks").countDocuments({}), 0);
+  });
+
+  test.it("should wait for lock held by another instance", async () => {
+    const db = await context.mongo.getDatabase();
+    const locks = db.collection("migration_locks");
+
+    await locks.insertOne({
+      _id: "migrations",
+      owner: "other-instance",
+      locked_until: new Date(Date.now() + 3000),
+    });
+
+    const output = await context.command.runCommand("migrate");
+
+    assert.ok(output.includes("waiting for lock held by another instance"));
+    assert.ok(output.includes("database is up to date"));
+    assert.equal(await locks.countDocuments({}), 0);
+  });
+});
+
+context.test.eachTopology("object_ids migration", () => {
+  test.it("should convert string ids and keep indexes", async () => {
+    const db = await context.mongo.getDatabase();
+    const users = db.collection("users");
+    const userId = new ObjectId();
+
+    await users.insertOne({
+      _id: userId.toHexString(),
+      email: `${userId.toHexString()}@test.com`,
+      role: "User",
+    });
+    await db.collection("migrations").deleteOne({ _id: 2 });
+
+    await context.command.runCommand("migrate");
+
+    const user = await users.findOne({ _id: userId });
+    assert.ok(user);
+    assert.equal(await users.countDocuments({ _id: { $type: "string" } }), 0);
+
+    const indexes = await users.indexes();
+    assert.ok(indexes.some((index) => index.unique && index.key.email));
+  });
+});
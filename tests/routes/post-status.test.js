@@ -0,0 +1,106 @@
+import test from "node:test";
+import assert from "node:assert";
+import context from "../_context/index.js";
+
+test.describe("post status", () => {
+  let authorData;
+  let readerData;
+
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.before(async () => {
+    authorData = await context.user.registerUser();
+    readerData = await context.user.registerUser();
+  });
+
+  async function listIds(token) {
+    const result = await context.api({ token }).get("/posts");
+    return result.data.items.map((p) => p._id);
+  }
+
+  test.it("post should be published by default", async () => {
+    const result = await context.post.createPost(authorData.token);
+
+    assert.equal(result.data.status, "published");
+    assert.ok(!isNaN(Date.parse(result.data.publish_at)));
+    assert.ok((await listIds()).includes(result.data._id));
+  });
+
+  test.it("draft should be visible only to author", async () => {
+    const draft = await context.post.createPost(authorData.token, {
+      status: "draft",
+    });
+    assert.equal(draft.data.publish_at, null);
+
+    assert.ok(!(await listIds()).includes(draft.data._id));
+    assert.ok(!(await listIds(readerData.token)).includes(draft.data._id));
+    assert.ok((await listIds(authorData.token)).includes(draft.data._id));
+
+    const error = await context
+      .api({ token: readerData.token })
+      .get(`/posts/${draft.data._id}`)
+      .catch((e) => e);
+    assert.equal(error.status, 404);
+
+    const drafts = await context
+      .api({ token: authorData.token })
+      .get("/posts", { params: { status: "draft" } });
+    assert.deepEqual(
+      drafts.data.items.map((p) => p._id),
+      [draft.data._id],
+    );
+  });
+
+  test.it("draft should become visible once published", async () => {
+    const draft = await context.post.createPost(authorData.token, {
+      status: "draft",
+    });
+
+    const result = await context
+      .api({ token: authorData.token })
+      .patch(`/posts/${draft.data._id}`, { status: "published" });
+
+    assert.equal(result.data.status, "published");
+    assert.ok(result.data.publish_at);
+    assert.ok((await listIds()).includes(draft.data._id));
+  });
+
+  test.it("scheduled post should become visible at its time", async () => {
+    const publishAt = new Date(Date.now() + 2000).toISOString();
+    const scheduled = await context.post.createPost(authorData.token, {
+      status: "scheduled",
+      publish_at: publishAt,
+    });
+
+    assert.ok(!(await listIds()).includes(scheduled.data._id));
+
+    await new Promise((resolve) => setTimeout(resolve, 2500));
+
+    assert.ok((await listIds()).includes(scheduled.data._id));
+  });
+
+  test.it("scheduled post should require future publish_at", async () => {
+    const missing = await context.post
+      .createPost(authorData.token, { status: "scheduled" })
+      .catch((e) => e);
+    assert.equal(missing.status, 400);
+
+    const past = await context.post.createPost(authorData.token, {
+      status: "scheduled",
+      publish_at: new Date(Date.now() - 1000).toISOString(),
+    }).catch((e) => e);
+    assert.equal(past.status, 400);
+  });
+
+  test.it("archived post should be hidden from others", async () => {
+    const post = await context.post.createPost(authorData.token);
+
+    await context
+      .api({ token: authorData.token })
+      .patch(`/posts/${post.data._id}`, { status: "archived" });
+
+    assert.ok(!(await listIds()).includes(post.data._id));
+    assert.ok((await listIds(authorData.token)).includes(post.data._id));
+  });
+});
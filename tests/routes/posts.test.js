@@ -1,5 +1,6 @@
 import test from "node:test";
 import assert from "node:assert";
+import { ObjectId } from "mongodb";
 import context from "../_context/index.js";
 
 test.describe("/posts", () => {
@@ -14,7 +15,7 @@ test.describe("/posts", () => {
 
   test.it("get /posts should return no posts", async () => {
     const result = await context.api().get("/posts");
-    assert.deepEqual(result.data, []);
+    assert.deepEqual(result.data, { items: [], next_cursor: null });
   });
 
   context.test.unauthorized({
@@ -23,20 +24,276 @@ test.describe("/posts", () => {
     data: { title: "Some title", content: "Some content" },
   });
 
+  test.it("post /posts should reject empty title", async () => {
+    const error = await context
+      .api({ token: registerData.token })
+      .post("/posts", { title: "   ", content: "Some content" })
+      .catch((e) => e);
+
+    assert.equal(error.status, 422);
+    assert.ok(error.response.data.details.title);
+  });
+
   test.it("post /posts should create post for authorized user", async () => {
     const result = await context
       .api({ token: registerData.token })
       .post("/posts", {
-        title: "Some title",
+        title: "  Some title  ",
         content: "Some content",
       });
 
     assert.ok(result.data._id);
     assert.equal(result.data.title, "Some title");
     assert.equal(result.data.user_id, registerData.user._id);
+    assert.equal(result.data.created_by, registerData.user._id);
+    assert.ok(!isNaN(Date.parse(result.data.created_at)));
 
     const getAllResult = await context.api().get("/posts");
-    assert.deepEqual(getAllResult.data.length, 1);
-    assert.deepEqual(getAllResult.data[0]._id, result.data._id);
+    assert.deepEqual(getAllResult.data.items.length, 1);
+    assert.deepEqual(getAllResult.data.items[0]._id, result.data._id);
+
+    const db = await context.mongo.getDatabase();
+    const storedPost = await db
+      .collection("posts")
+      .findOne({ _id: new ObjectId(result.data._id) });
+    assert.ok(storedPost.user_id instanceof ObjectId);
+  });
+
+  test.it("get /posts/:id should return 404 for missing post", async () => {
+    const error = await context
+      .api()
+      .get("/posts/000000000000000000000000")
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+    assert.equal(error.response.data.code, "not_found");
+  });
+
+  test.it("get /posts/:id should return 400 for malformed id", async () => {
+    const error = await context
+      .api()
+      .get("/posts/not-an-id")
+      .catch((e) => e);
+
+    assert.equal(error.status, 400);
+    assert.equal(error.response.data.code, "bad_request");
+  });
+
+  test.describe("modification", () => {
+    let post;
+    let otherUserData;
+
+    test.before(async () => {
+      otherUserData = await context.user.registerUser();
+    });
+
+    test.beforeEach(async () => {
+      const result = await context
+        .api({ token: registerData.token })
+        .post("/posts", {
+          title: "Original title",
+          content: "Original content",
+        });
+      post = result.data;
+    });
+
+    test.it("patch /posts/:id should update only provided fields", async () => {
+      const result = await context
+        .api({ token: registerData.token })
+        .patch(`/posts/${post._id}`, { title: " New title " });
+
+      assert.equal(result.data.title, "New title");
+      assert.equal(result.data.content, "Original content");
+    });
+
+    test.it("patch /posts/:id should accept longest multibyte content", async () => {
+      const content = "😀".repeat(20000);
+      const result = await context
+        .api({ token: registerData.token })
+        .patch(`/posts/${post._id}`, { title: "😀".repeat(200), content });
+
+      assert.equal(result.data.content, content);
+    });
+
+    test.it("patch /posts/:id should accept longest escaped content", async () => {
+      const escaped = "\\ud83d\\ude00";
+      const title = escaped.repeat(200);
+      const content = escaped.repeat(20000);
+      const body = `{ "title": "${title}", "content": "${content}" }`;
+      const result = await context
+        .api({ token: registerData.token })
+        .patch(`/posts/${post._id}`, body, {
+          headers: { "Content-Type": "application/json" },
+        });
+
+      assert.equal(result.data.content, "😀".repeat(20000));
+    });
+
+    test.it("patch /posts/:id should reject empty title", async () => {
+      const error = await context
+        .api({ token: registerData.token })
+        .patch(`/posts/${post._id}`, { title: "" })
+        .catch((e) => e);
+
+      assert.equal(error.status, 422);
+    });
+
+    test.it("patch /posts/:id should be forbidden for other user", async () => {
+      const error = await context
+        .api({ token: otherUserData.token })
+        .patch(`/posts/${post._id}`, { title: "Hijacked" })
+        .catch((e) => e);
+
+      assert.equal(error.status, 403);
+      assert.equal(error.response.data.code, "forbidden");
+    });
+
+    test.it("patch /posts/:id should be allowed for admin", async () => {
+      const adminData = await context.user.registerUser({ role: "Admin" });
+
+      const result = await context
+        .api({ token: adminData.token })
+        .patch(`/posts/${post._id}`, { content: "Moderated" });
+
+      assert.equal(result.data.content, "Moderated");
+      assert.equal(result.data.created_by, registerData.user._id);
+      assert.equal(result.data.updated_by, adminData.user._id);
+      assert.ok(
+        new Date(result.data.updated_at) >= new Date(result.data.created_at),
+      );
+    });
+
+    test.it("delete /posts/:id should be forbidden for other user", async () => {
+      const error = await context
+        .api({ token: otherUserData.token })
+        .delete(`/posts/${post._id}`)
+        .catch((e) => e);
+
+      assert.equal(error.status, 403);
+    });
+
+    test.it("delete /posts/:id should remove post of author", async () => {
+      const result = await context
+        .api({ token: registerData.token })
+        .delete(`/posts/${post._id}`);
+
+      assert.equal(result.status, 204);
+
+      const error = await context
+        .api()
+        .get(`/posts/${post._id}`)
+        .catch((e) => e);
+
+      assert.equal(error.status, 404);
+    });
+
+    test.it("deleted post should be listed and restored by admin", async () => {
+      const adminData = await context.user.registerUser({ role: "Admin" });
+
+      await context
+        .api({ token: registerData.token })
+        .delete(`/posts/${post._id}`);
+
+      const deletedResult = await context
+        .api({ token: adminData.token })
+        .get("/posts/deleted");
+
+      const deletedPost = deletedResult.data.items.find(
+        (p) => p._id === post._id,
+      );
+      assert.ok(deletedPost);
+      assert.equal(deletedPost.deleted_by, registerData.user._id);
+
+      const restoreResult = await context
+        .api({ token: adminData.token })
+        .post(`/posts/${post._id}/restore`);
+
+      assert.equal(restoreResult.data.deleted_at, null);
+
+      const result = await context.api().get(`/posts/${post._id}`);
+      assert.equal(result.data._id, post._id);
+    });
+
+    test.it("post /posts/:id/restore should be admin only", async () => {
+      await context
+        .api({ token: registerData.token })
+        .delete(`/posts/${post._id}`);
+
+      const error = await context
+        .api({ token: registerData.token })
+        .post(`/posts/${post._id}/restore`)
+        .catch((e) => e);
+
+      assert.equal(error.status, 401);
+    });
+  });
+
+  test.describe("pagination", () => {
+    let authorData;
+    const createdIds = [];
+
+    test.before(async () => {
+      authorData = await context.user.registerUser();
+
+      for (const title of ["First", "Second", "Third"]) {
+        const result = await context
+          .api({ token: authorData.token })
+          .post("/posts", { title, content: "Some content" });
+        createdIds.push(result.data._id);
+      }
+    });
+
+    test.it("get /posts should return pages by cursor", async () => {
+      const author = authorData.user._id;
+
+      const firstPage = await context
+        .api()
+        .get("/posts", { params: { author, limit: 2 } });
+
+      assert.deepEqual(
+        firstPage.data.items.map((p) => p._id),
+        [createdIds[2], createdIds[1]],
+      );
+      assert.ok(firstPage.data.next_cursor);
+
+      const secondPage = await context.api().get("/posts", {
+        params: { author, limit: 2, cursor: firstPage.data.next_cursor },
+      });
+
+      assert.deepEqual(
+        secondPage.data.items.map((p) => p._id),
+        [createdIds[0]],
+      );
+      assert.equal(secondPage.data.next_cursor, null);
+    });
+
+    test.it("get /posts should sort oldest first", async () => {
+      const result = await context.api().get("/posts", {
+        params: { author: authorData.user._id, sort: "oldest" },
+      });
+
+      assert.deepEqual(
+        result.data.items.map((p) => p._id),
+        createdIds,
+      );
+    });
+
+    test.it("get /posts should reject limit above maximum", async () => {
+      const error = await context
+        .api()
+        .get("/posts", { params: { limit: 1000 } })
+        .catch((e) => e);
+
+      assert.equal(error.status, 400);
+    });
+
+    test.it("get /posts should reject malformed cursor", async () => {
+      const error = await context
+        .api()
+        .get("/posts", { params: { cursor: "not-a-cursor" } })
+        .catch((e) => e);
+
+      assert.equal(error.status, 400);
+    });
   });
 });
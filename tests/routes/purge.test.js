@@ -0,0 +1,95 @@
+import test from "node:test";
+import assert from "node:assert";
+import { ObjectId } from "mongodb";
+import context from "../_context/index.js";
+
+test.describe("purge-deleted command", () => {
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.it("should remove everything created by purged user", async () => {
+    const adminData = await context.user.registerUser({ role: "Admin" });
+    const removedData = await context.user.registerUser();
+    const otherData = await context.user.registerUser();
+    const removedApi = context.api({ token: removedData.token });
+    const otherApi = context.api({ token: otherData.token });
+
+    const removedPost = await context.post.createPost(removedData.token, {
+      title: "Removed post",
+    });
+    const otherPost = await context.post.createPost(otherData.token, {
+      title: "Other post",
+    });
+
+    await otherApi.post(`/posts/${removedPost.data._id}/comments`, {
+      content: "Comment on removed post",
+    });
+    const removedComment = await removedApi.post(
+      `/posts/${otherPost.data._id}/comments`,
+      { content: "Comment of removed user" },
+    );
+    await otherApi.post(`/posts/${otherPost.data._id}/comments`, {
+      content: "Reply to removed user",
+      parent_id: removedComment.data._id,
+    });
+    const keptComment = await otherApi.post(
+      `/posts/${otherPost.data._id}/comments`,
+      { content: "Kept comment" },
+    );
+    await removedApi.post(`/posts/${otherPost.data._id}/comments`, {
+      content: "Reply of removed user",
+      parent_id: keptComment.data._id,
+    });
+    await removedApi.put(`/posts/${otherPost.data._id}/like`);
+
+    await context
+      .api({ token: adminData.token })
+      .delete(`/users/${removedData.user._id}`);
+
+    await context.command.runCommand("purge-deleted", [], {
+      DELETED_RETENTION: "0",
+    });
+
+    const db = await context.mongo.getDatabase();
+    const userId = new ObjectId(removedData.user._id);
+
+    assert.equal(
+      await db.collection("users").countDocuments({ _id: userId }),
+      0,
+    );
+    for (const collection of [
+      "user_auths",
+      "posts",
+      "comments",
+      "reactions",
+      "refresh_tokens",
+      "revoked_tokens",
+    ]) {
+      assert.equal(
+        await db.collection(collection).countDocuments({ user_id: userId }),
+        0,
+        collection,
+      );
+    }
+
+    const comments = await db
+      .collection("comments")
+      .find({ post_id: new ObjectId(otherPost.data._id) })
+      .toArray();
+    assert.deepEqual(
+      comments.map((c) => c.content),
+      ["Kept comment"],
+    );
+    assert.equal(comments[0].reply_count, 0);
+    assert.equal(
+      await db
+        .collection("comments")
+        .countDocuments({ post_id: new ObjectId(removedPost.data._id) }),
+      0,
+    );
+
+    const post = await otherApi.get(`/posts/${otherPost.data._id}`);
+    assert.equal(post.data.comment_count, 1);
+    assert.equal(post.data.like_count, 0);
+  });
+});
@@ -0,0 +1,24 @@
+import test from "node:test";
+import assert from "node:assert";
+import { v4 as uuid } from "uuid";
+import context from "../_context/index.js";
+
+const env = { FAIL_POINT: "registration_after_user_insert" };
+
+context.test.eachTopology("registration failure", { env }, () => {
+  test.it("should not leave partial user records", async () => {
+    const email = `${uuid()}@test.com`;
+
+    const error = await context
+      .api()
+      .post("/users/register", { email, password: "password123" })
+      .catch((e) => e);
+
+    assert.equal(error.status, 500);
+
+    const db = await context.mongo.getDatabase();
+
+    assert.equal(await db.collection("users").countDocuments({ email }), 0);
+    assert.equal(await db.collection("user_auths").countDocuments({}), 0);
+  });
+});
@@ -0,0 +1,50 @@
+import test from "node:test";
+import assert from "node:assert";
+import { ObjectId } from "mongodb";
+import context from "../_context/index.js";
+
+test.describe("repair-user-auths command", () => {
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.it("should relink quoted user ids and report unrecoverable ones", async () => {
+    const password = "1qaz!QAZ";
+    const registerData = await context.user.registerUser({ password });
+    const userId = registerData.user._id;
+
+    const db = await context.mongo.getDatabase();
+    const userAuths = db.collection("user_auths");
+
+    // shape records had before registration stored user id properly
+    await userAuths.updateOne(
+      { user_id: new ObjectId(userId) },
+      { $set: { user_id: `"${userId}"` } },
+    );
+    await userAuths.insertMany([
+      { _id: new ObjectId(), user_id: "not-an-id", password_hash: "" },
+      {
+        _id: new ObjectId(),
+        user_id: `"${new ObjectId().toHexString()}"`,
+        password_hash: "",
+      },
+    ]);
+
+    const loginError = await context
+      .api()
+      .post("/users/login", { email: registerData.user.email, password })
+      .catch((e) => e);
+    assert.equal(loginError.status, 401);
+
+    const output = await context.command.runCommand("repair-user-auths");
+
+    assert.ok(output.includes("checked 3, repaired 1, unrecoverable 2"));
+
+    const repaired = await userAuths.findOne({ user_id: new ObjectId(userId) });
+    assert.ok(repaired);
+
+    const loginResult = await context
+      .api()
+      .post("/users/login", { email: registerData.user.email, password });
+    assert.equal(loginResult.data.user._id, userId);
+  });
+});
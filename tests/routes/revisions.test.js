@@ -0,0 +1,171 @@
+import test from "node:test";
+import assert from "node:assert";
+import { ObjectId } from "mongodb";
+import context from "../_context/index.js";
+
+test.describe("/posts/:id/revisions", () => {
+  let authorData;
+  let otherUserData;
+  let post;
+
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.before(async () => {
+    authorData = await context.user.registerUser();
+    otherUserData = await context.user.registerUser();
+
+    const result = await context.post.createPost(authorData.token, {
+      title: "First title",
+      content: "line one\nline two",
+    });
+    post = result.data;
+
+    const api = context.api({ token: authorData.token });
+    await api.patch(`/posts/${post._id}`, {
+      content: "line one\nline two changed",
+    });
+    // status change is not a revision
+    await api.patch(`/posts/${post._id}`, { status: "archived" });
+    await api.patch(`/posts/${post._id}`, {
+      title: "Second title",
+      status: "published",
+    });
+  });
+
+  test.it("should list revisions newest first", async () => {
+    const result = await context.api().get(`/posts/${post._id}/revisions`);
+
+    assert.deepEqual(
+      result.data.items.map((r) => [r.rev, r.title]),
+      [
+        [3, "Second title"],
+        [2, "First title"],
+        [1, "First title"],
+      ],
+    );
+    assert.equal(result.data.items[2].content, "line one\nline two");
+
+    const current = await context.api().get(`/posts/${post._id}`);
+    assert.equal(current.data.revision, 3);
+  });
+
+  test.it("should diff two revisions by lines", async () => {
+    const result = await context
+      .api()
+      .get(`/posts/${post._id}/revisions/diff`, {
+        params: { from: 1, to: 2 },
+      });
+
+    assert.deepEqual(result.data.content, [
+      { change: "equal", text: "line one" },
+      { change: "delete", text: "line two" },
+      { change: "insert", text: "line two changed" },
+    ]);
+    assert.deepEqual(result.data.title, [
+      { change: "equal", text: "First title" },
+    ]);
+  });
+
+  test.it("should return 404 for missing revision", async () => {
+    const error = await context
+      .api()
+      .get(`/posts/${post._id}/revisions/42`)
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+  });
+
+  test.it("restore should be forbidden for other user", async () => {
+    const error = await context
+      .api({ token: otherUserData.token })
+      .post(`/posts/${post._id}/revisions/1/restore`)
+      .catch((e) => e);
+
+    assert.equal(error.status, 403);
+  });
+
+  test.it("restore should save revision content as new revision", async () => {
+    const result = await context
+      .api({ token: authorData.token })
+      .post(`/posts/${post._id}/revisions/1/restore`);
+
+    assert.equal(result.data.title, "First title");
+    assert.equal(result.data.content, "line one\nline two");
+    assert.equal(result.data.revision, 4);
+
+    const revision = await context
+      .api()
+      .get(`/posts/${post._id}/revisions/4`);
+    assert.equal(revision.data.created_by, authorData.user._id);
+  });
+
+  test.it("restore should be allowed for admin", async () => {
+    const adminData = await context.user.registerUser({ role: "Admin" });
+
+    const result = await context
+      .api({ token: adminData.token })
+      .post(`/posts/${post._id}/revisions/3/restore`);
+
+    assert.equal(result.data.title, "Second title");
+    assert.equal(result.data.revision, 5);
+  });
+});
+
+context.test.eachTopology("post revisions", ({ replSet }) => {
+  test.it("should keep one revision per concurrent edit", async () => {
+    const authorData = await context.user.registerUser();
+    const api = context.api({ token: authorData.token });
+
+    const created = await context.post.createPost(authorData.token);
+    const postId = created.data._id;
+
+    const results = await Promise.all(
+      ["First", "Second", "Third"].map((content) =>
+        api.patch(`/posts/${postId}`, { content }).catch((e) => e),
+      ),
+    );
+    for (const result of results) {
+      assert.ok([200, 409].includes(result.status));
+    }
+
+    const post = await context.api().get(`/posts/${postId}`);
+    const revisions = await context.api().get(`/posts/${postId}/revisions`);
+
+    assert.equal(revisions.data.items.length, post.data.revision);
+    assert.equal(revisions.data.items[0].rev, post.data.revision);
+    assert.equal(revisions.data.items[0].content, post.data.content);
+  });
+
+  // replica set writes revision in the same transaction, so it never leaves them
+  if (!replSet) {
+    test.it("should replace revision left by failed update", async () => {
+      const authorData = await context.user.registerUser();
+      const api = context.api({ token: authorData.token });
+
+      const created = await context.post.createPost(authorData.token);
+      const postId = created.data._id;
+
+      const db = await context.mongo.getDatabase();
+      await db.collection("post_revisions").insertOne({
+        post_id: new ObjectId(postId),
+        rev: 2,
+        title: "Some title",
+        content: "Never saved",
+        created_at: new Date(Date.now() - 10 * 60 * 1000),
+        created_by: null,
+      });
+
+      const before = await context.api().get(`/posts/${postId}/revisions`);
+      assert.deepEqual(
+        before.data.items.map((r) => r.rev),
+        [1],
+      );
+
+      await api.patch(`/posts/${postId}`, { content: "Saved" });
+
+      const after = await context.api().get(`/posts/${postId}/revisions/2`);
+      assert.equal(after.data.content, "Saved");
+    });
+  }
+});
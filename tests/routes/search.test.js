@@ -0,0 +1,96 @@
+import test from "node:test";
+import assert from "node:assert";
+import context from "../_context/index.js";
+
+test.describe("/posts/search", () => {
+  let authorData;
+  let otherAuthorData;
+
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.before(async () => {
+    authorData = await context.user.registerUser();
+    otherAuthorData = await context.user.registerUser();
+
+    const posts = [
+      [authorData, "Rust ownership", "Ownership rules explained"],
+      [authorData, "Borrow checker", "Why rust rejects <this> code"],
+      [otherAuthorData, "Cooking pasta", "Boil water and add salt"],
+    ];
+
+    for (const [author, title, content] of posts) {
+      await context.post.createPost(author.token, { title, content });
+    }
+  });
+
+  test.it("should rank title matches first and highlight them", async () => {
+    const result = await context
+      .api()
+      .get("/posts/search", { params: { q: "rust" } });
+
+    const items = result.data.items;
+
+    assert.deepEqual(
+      items.map((p) => p.title),
+      ["Rust ownership", "Borrow checker"],
+    );
+    assert.ok(items[0].score > items[1].score);
+    assert.deepEqual(items[0].highlights.title, ["<mark>Rust</mark> ownership"]);
+    assert.deepEqual(items[1].highlights.content, [
+      "Why <mark>rust</mark> rejects &lt;this&gt; code",
+    ]);
+  });
+
+  test.it("should highlight match crossing snippet edge", async () => {
+    const filler = "a".repeat(34);
+    await context.post.createPost(authorData.token, {
+      title: "Mascot",
+      content: `ferris ${filler} ferris and more words after it`,
+    });
+
+    const result = await context
+      .api()
+      .get("/posts/search", { params: { q: "ferris" } });
+
+    assert.deepEqual(result.data.items[0].highlights.content, [
+      `<mark>ferris</mark> ${filler} <mark>ferris</mark>…`,
+    ]);
+  });
+
+  test.it("should filter by author", async () => {
+    const result = await context.api().get("/posts/search", {
+      params: { q: "pasta rust", author: otherAuthorData.user._id },
+    });
+
+    assert.deepEqual(
+      result.data.items.map((p) => p.title),
+      ["Cooking pasta"],
+    );
+  });
+
+  test.it("should return pages by cursor", async () => {
+    const firstPage = await context
+      .api()
+      .get("/posts/search", { params: { q: "rust", limit: 1 } });
+
+    assert.equal(firstPage.data.items.length, 1);
+    assert.ok(firstPage.data.next_cursor);
+
+    const secondPage = await context.api().get("/posts/search", {
+      params: { q: "rust", limit: 1, cursor: firstPage.data.next_cursor },
+    });
+
+    assert.equal(secondPage.data.items[0].title, "Borrow checker");
+    assert.equal(secondPage.data.next_cursor, null);
+  });
+
+  test.it("should reject empty query", async () => {
+    const error = await context
+      .api()
+      .get("/posts/search", { params: { q: "  " } })
+      .catch((e) => e);
+
+    assert.equal(error.status, 400);
+  });
+});
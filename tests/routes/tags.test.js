@@ -0,0 +1,86 @@
+import test from "node:test";
+import assert from "node:assert";
+import context from "../_context/index.js";
+
+test.describe("tags", () => {
+  let authorData;
+
+  test.before(async (t) => await context.bootstrap());
+  test.after(async (t) => await context.shutdown());
+
+  test.before(async () => {
+    authorData = await context.user.registerUser();
+  });
+
+  test.it("post /posts should normalize tags", async () => {
+    const result = await context.post.createPost(authorData.token, {
+      tags: ["  #Rust  Lang ", "rust-lang", "Web", ""],
+    });
+
+    assert.deepEqual(result.data.tags, ["rust-lang", "web"]);
+  });
+
+  test.it("post /posts should reject invalid tags", async () => {
+    const error = await context.post
+      .createPost(authorData.token, { tags: ["c++"] })
+      .catch((e) => e);
+
+    assert.equal(error.status, 422);
+    assert.ok(error.response.data.details.tags);
+  });
+
+  test.it("get /posts should filter by tag", async () => {
+    const tagged = await context.post.createPost(authorData.token, {
+      tags: ["filtering"],
+    });
+    await context.post.createPost(authorData.token, { tags: ["other"] });
+
+    const result = await context
+      .api()
+      .get("/posts", { params: { tag: "#Filtering" } });
+
+    assert.deepEqual(
+      result.data.items.map((p) => p._id),
+      [tagged.data._id],
+    );
+  });
+
+  test.it("get /tags should count published posts by tag", async () => {
+    await context.post.createPost(authorData.token, {
+      tags: ["counted", "popular"],
+    });
+    await context.post.createPost(authorData.token, { tags: ["popular"] });
+    await context.post.createPost(authorData.token, {
+      tags: ["popular"],
+      status: "draft",
+    });
+
+    const result = await context.api().get("/tags");
+    const counts = Object.fromEntries(
+      result.data.map(({ tag, count }) => [tag, count]),
+    );
+
+    assert.equal(counts.popular, 2);
+    assert.equal(counts.counted, 1);
+    assert.equal(result.data[0].tag, "popular");
+  });
+
+  test.it("patch /posts/:id should replace tags", async () => {
+    const post = await context.post.createPost(authorData.token, {
+      tags: ["before"],
+    });
+
+    const result = await context
+      .api({ token: authorData.token })
+      .patch(`/posts/${post.data._id}`, { tags: ["After"] });
+
+    assert.deepEqual(result.data.tags, ["after"]);
+  });
+
+  test.it("tags should be indexed", async () => {
+    const db = await context.mongo.getDatabase();
+    const indexes = await db.collection("posts").indexes();
+
+    assert.ok(indexes.some((i) => i.name === "tags_1"));
+  });
+});
@@ -1,5 +1,7 @@
 import test from "node:test";
 import assert from "node:assert";
+import crypto from "node:crypto";
+import { ObjectId } from "mongodb";
 import context from "../_context/index.js";
 
 test.describe("/users", () => {
@@ -11,11 +13,38 @@ test.describe("/users", () => {
   test.after(async (t) => await context.shutdown());
 
   test.it("post /login should fail for not existing user", async () => {
-    await assert.rejects(async () =>
-      context.api().post("/users/login", {
+    const error = await context
+      .api()
+      .post("/users/login", {
         email,
         password,
-      }),
+      })
+      .catch((e) => e);
+
+    assert.equal(error.status, 401);
+  });
+
+  test.it("post /register should reject invalid email", async () => {
+    const error = await context
+      .api()
+      .post("/users/register", { email: "not-an-email", password })
+      .catch((e) => e);
+
+    assert.equal(error.status, 422);
+    assert.equal(error.response.data.code, "validation_failed");
+    assert.ok(error.response.data.details.email);
+  });
+
+  test.it("post /register should reject weak password", async () => {
+    const error = await context
+      .api()
+      .post("/users/register", { email: "weak@email.com", password: "short" })
+      .catch((e) => e);
+
+    assert.equal(error.status, 422);
+    assert.equal(
+      error.response.data.details.password[0].code,
+      "password_too_short",
     );
   });
 
@@ -37,6 +66,34 @@ test.describe("/users", () => {
     token = result.data.token;
   });
 
+  test.it("post /register should reject already registered email", async () => {
+    const error = await context
+      .api()
+      .post("/users/register", { email: ` ${email.toUpperCase()} `, password })
+      .catch((e) => e);
+
+    assert.equal(error.status, 409);
+    assert.equal(error.response.data.code, "conflict");
+  });
+
+  test.it("post /login should accept email in different case", async () => {
+    const result = await context.api().post("/users/login", {
+      email: email.toUpperCase(),
+      password,
+    });
+
+    assert.equal(result.data.user.email, email);
+  });
+
+  test.it("post /register should store argon2id password hash", async () => {
+    const db = await context.mongo.getDatabase();
+    const userAuths = await db.collection("user_auths").find({}).toArray();
+
+    assert.equal(userAuths.length, 1);
+    assert.match(userAuths[0].password_hash, /^\$argon2id\$/);
+    assert.notEqual(userAuths[0].password_hash, password);
+  });
+
   test.it("get /me should return myself by token", async () => {
     const result = await context.api({ token }).get("/users/me");
 
@@ -53,6 +110,126 @@ test.describe("/users", () => {
     assert.equal(result.data.user.email, email);
   });
 
+  test.it("post /login should keep current password hash", async () => {
+    const db = await context.mongo.getDatabase();
+    const storedHash = async () =>
+      (await db.collection("user_auths").findOne({})).password_hash;
+
+    const hashBefore = await storedHash();
+
+    for (let i = 0; i < 2; i++) {
+      await context.api().post("/users/login", { email, password });
+    }
+
+    assert.equal(await storedHash(), hashBefore);
+  });
+
+  test.it("post /login should fail for wrong password", async () => {
+    const error = await context
+      .api()
+      .post("/users/login", {
+        email,
+        password: "wrong-password",
+      })
+      .catch((e) => e);
+
+    assert.equal(error.status, 401);
+  });
+
+  test.it("post /login should upgrade legacy sha256 hash", async () => {
+    const legacyEmail = "legacy@email.com";
+    const userId = new ObjectId();
+    const legacyHash = crypto
+      .createHash("sha256")
+      .update(password + "dontusedefaultsalt")
+      .digest("hex");
+
+    const audit = {
+      created_at: new Date(),
+      created_by: userId,
+      updated_at: new Date(),
+      updated_by: userId,
+    };
+
+    const db = await context.mongo.getDatabase();
+    await db
+      .collection("users")
+      .insertOne({ _id: userId, email: legacyEmail, role: "User", ...audit });
+    await db.collection("user_auths").insertOne({
+      _id: new ObjectId(),
+      user_id: userId,
+      password_hash: legacyHash,
+      ...audit,
+    });
+
+    const result = await context.api().post("/users/login", {
+      email: legacyEmail,
+      password,
+    });
+
+    assert.ok(result.data.token);
+
+    const userAuth = await db
+      .collection("user_auths")
+      .findOne({ user_id: userId });
+    assert.match(userAuth.password_hash, /^\$argon2id\$/);
+
+    await db.collection("users").deleteOne({ _id: userId });
+    await db.collection("user_auths").deleteOne({ user_id: userId });
+  });
+
+  test.it("post /token/refresh should rotate refresh token", async () => {
+    const loginResult = await context.api().post("/users/login", {
+      email,
+      password,
+    });
+
+    const result = await context.api().post("/users/token/refresh", {
+      refresh_token: loginResult.data.refresh_token,
+    });
+
+    assert.ok(result.data.token);
+    assert.ok(result.data.refresh_token);
+    assert.notEqual(result.data.refresh_token, loginResult.data.refresh_token);
+
+    const meResult = await context
+      .api({ token: result.data.token })
+      .get("/users/me");
+    assert.equal(meResult.data.email, email);
+  });
+
+  test.it(
+    "post /token/refresh should revoke token family on reuse",
+    async () => {
+      const loginResult = await context.api().post("/users/login", {
+        email,
+        password,
+      });
+      const oldRefreshToken = loginResult.data.refresh_token;
+
+      const rotated = await context.api().post("/users/token/refresh", {
+        refresh_token: oldRefreshToken,
+      });
+
+      const reuseError = await context
+        .api()
+        .post("/users/token/refresh", { refresh_token: oldRefreshToken })
+        .catch((e) => e);
+      assert.equal(reuseError.status, 401);
+
+      const revokedError = await context
+        .api()
+        .post("/users/token/refresh", {
+          refresh_token: rotated.data.refresh_token,
+        })
+        .catch((e) => e);
+      assert.equal(revokedError.status, 401);
+    },
+  );
+
+  context.test.unauthorized({ method: "get", url: "/users" });
+  context.test.unauthorized({ method: "get", url: "/users/me" });
+
   context.test.unauthorizedForRole({
     method: "get",
     url: "/users",
@@ -60,6 +237,13 @@ test.describe("/users", () => {
     token: () => token,
   });
 
+  context.test.unauthorizedForRole({
+    method: "get",
+    url: "/users/000000000000000000000000",
+    role: "User",
+    token: () => token,
+  });
+
   test.it("get /users should return all users for admin", async () => {
     let adminRegisterData = await context.user.registerUser({ role: "Admin" });
 
@@ -67,10 +251,173 @@ test.describe("/users", () => {
       .api({ token: adminRegisterData.token })
       .get("/users");
 
-    const users = result.data;
+    const users = result.data.items;
 
     assert.ok(users.find((u) => u._id === adminRegisterData.user._id));
     assert.ok(users.find((u) => u.email === email));
     assert.equal(users.length, 2);
   });
+
+  test.it("get /users/:id should return 404 for missing user", async () => {
+    const adminRegisterData = await context.user.registerUser({
+      role: "Admin",
+    });
+
+    const error = await context
+      .api({ token: adminRegisterData.token })
+      .get("/users/000000000000000000000000")
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+    assert.equal(error.response.data.code, "not_found");
+  });
+
+  test.it("unknown /users path should return 404", async () => {
+    const error = await context
+      .api()
+      .get("/users/unknown/path")
+      .catch((e) => e);
+
+    assert.equal(error.status, 404);
+    assert.equal(error.response.data.code, "not_found");
+  });
+
+  test.it("get /users/:id should return 400 for malformed id", async () => {
+    const adminRegisterData = await context.user.registerUser({
+      role: "Admin",
+    });
+
+    const error = await context
+      .api({ token: adminRegisterData.token })
+      .get("/users/not-an-id")
+      .catch((e) => e);
+
+    assert.equal(error.status, 400);
+    assert.equal(error.response.data.code, "bad_request");
+  });
+
+  context.test.unauthorized({ method: "post", url: "/users/logout" });
+
+  test.it("post /logout should revoke current token", async () => {
+    const registerData = await context.user.registerUser();
+    const api = context.api({ token: registerData.token });
+
+    await api.post("/users/logout", {
+      refresh_token: registerData.refresh_token,
+    });
+
+    const meError = await api.get("/users/me").catch((e) => e);
+    assert.equal(meError.status, 401);
+
+    const refreshError = await context
+      .api()
+      .post("/users/token/refresh", {
+        refresh_token: registerData.refresh_token,
+      })
+      .catch((e) => e);
+    assert.equal(refreshError.status, 401);
+  });
+
+  test.it("post /logout with all should revoke every token", async () => {
+    const registerData = await context.user.registerUser();
+    const loginResult = await context.api().post("/users/login", {
+      email: registerData.user.email,
+      password: "1qaz!QAZ",
+    });
+
+    await context
+      .api({ token: registerData.token })
+      .post("/users/logout", { all: true });
+
+    const meError = await context
+      .api({ token: loginResult.data.token })
+      .get("/users/me")
+      .catch((e) => e);
+    assert.equal(meError.status, 401);
+
+    const refreshError = await context
+      .api()
+      .post("/users/token/refresh", {
+        refresh_token: loginResult.data.refresh_token,
+      })
+      .catch((e) => e);
+    assert.equal(refreshError.status, 401);
+  });
+
+  test.it("post /logout with malformed body should return 400", async () => {
+    const registerData = await context.user.registerUser();
+
+    const error = await context
+      .api({ token: registerData.token })
+      .post("/users/logout", "{all", {
+        headers: { "Content-Type": "application/json" },
+      })
+      .catch((e) => e);
+    assert.equal(error.status, 400);
+
+    const meResult = await context
+      .api({ token: registerData.token })
+      .get("/users/me");
+    assert.equal(meResult.status, 200);
+  });
+
+  test.it("token issued right after logout with all should be valid", async () => {
+    const registerData = await context.user.registerUser();
+
+    await context
+      .api({ token: registerData.token })
+      .post("/users/logout", { all: true });
+
+    const loginResult = await context.api().post("/users/login", {
+      email: registerData.user.email,
+      password: "1qaz!QAZ",
+    });
+
+    const meResult = await context
+      .api({ token: loginResult.data.token })
+      .get("/users/me");
+    assert.equal(meResult.status, 200);
+  });
+
+  test.it("deleted user should not sign in until restored", async () => {
+    const adminData = await context.user.registerUser({ role: "Admin" });
+    const deletedEmail = "deleted@email.com";
+    const userData = await context.user.registerUser({
+      email: deletedEmail,
+      password,
+    });
+
+    const deleteResult = await context
+      .api({ token: adminData.token })
+      .delete(`/users/${userData.user._id}`);
+    assert.equal(deleteResult.status, 204);
+
+    const meError = await context
+      .api({ token: userData.token })
+      .get("/users/me")
+      .catch((e) => e);
+    assert.equal(meError.status, 401);
+
+    const loginError = await context
+      .api()
+      .post("/users/login", { email: deletedEmail, password })
+      .catch((e) => e);
+    assert.equal(loginError.status, 401);
+
+    const deletedResult = await context
+      .api({ token: adminData.token })
+      .get("/users/deleted");
+    assert.ok(
+      deletedResult.data.items.find((u) => u._id === userData.user._id),
+    );
+
+    await context
+      .api({ token: adminData.token })
+      .post(`/users/${userData.user._id}/restore`);
+
+    const loginResult = await context
+      .api()
+      .post("/users/login", { email: deletedEmail, password });
+    assert.ok(loginResult.data.token);
+  });
 });